@@ -1,6 +1,9 @@
 #![feature(destructuring_assignment)]
 #![feature(array_methods)]
+#![allow(clippy::too_many_arguments)]
 pub mod state;
 pub mod entrypoint;
 pub mod processor;
-mod merkle;
\ No newline at end of file
+mod merkle;
+#[cfg(test)]
+mod test_utils;
\ No newline at end of file
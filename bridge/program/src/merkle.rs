@@ -1,14 +1,19 @@
-use std::hash::Hash;
-
-use solana_program::{
-    msg,
-    pubkey::Pubkey,
-};
+use solana_program::pubkey::Pubkey;
 
 use lib::merkle::{amount_bytes};
 use lib::SOLANA_NETWORK;
+use lib::instructions::bridge::{SignedCreator, SignedRoyalties, TargetCall};
+use crate::state::OperationType;
 
 const SOLANA_NATIVE_DECIMALS: u8 = 9u8;
+// Separates the withdrawal index from the other optional fields of the signed transfer data
+const BITMAP_INDEX_TAG: &[u8] = b"bitmap";
+// Separates the relayer fee, so it can not be taken for the other optional fields
+const RELAYER_FEE_TAG: &[u8] = b"relayer_fee";
+// Distinguishes compressed NFT transfers from the NFT ones with the same token id
+const COMPRESSED_TAG: &[u8] = b"compressed";
+// Precedes the fields of the version 2 signed metadata, so version 1 transfers keep their hashes
+const METADATA_V2_TAG: &[u8] = b"metadata_v2";
 
 pub trait Data {
     fn get_operation(&self) -> Vec<u8>;
@@ -20,6 +25,8 @@ pub struct Content {
     pub receiver: [u8;32],
     pub program_id: [u8; 32],
     pub data: Vec<u8>,
+    // Target program, accounts and payload for WithdrawAndCall
+    pub call: Option<TargetCall>,
 }
 
 impl Content {
@@ -30,9 +37,15 @@ impl Content {
             network_to: String::from(SOLANA_NETWORK),
             program_id,
             data: data.get_operation(),
+            call: None,
         }
     }
 
+    pub fn with_call(mut self, call: Option<TargetCall>) -> Self {
+        self.call = call;
+        self
+    }
+
     pub fn hash(self) -> solana_program::keccak::Hash {
         let mut data = Vec::new();
         data.append(&mut Vec::from(self.data));
@@ -45,6 +58,53 @@ impl Content {
 
         data.append(&mut Vec::from(self.program_id.as_slice()));
 
+        if let Some(mut call) = self.call {
+            data.append(&mut Vec::from(call.program.as_ref()));
+            data.push(call.accounts.len() as u8);
+            for account in &call.accounts {
+                data.extend_from_slice(account.pubkey.as_ref());
+                data.push(account.is_writable as u8);
+            }
+            data.append(&mut call.payload);
+        }
+
+        solana_program::keccak::hash(data.as_slice())
+    }
+}
+
+// Content for the signed admin operations
+pub struct OperationContent {
+    pub nonce: u64,
+    // Bridge admin key, so the operation can not be replayed on another bridge admin with the same signers
+    pub contract: Pubkey,
+    pub network: String,
+    pub operation_type: OperationType,
+    pub data: Vec<u8>,
+}
+
+impl OperationContent {
+    pub fn new(nonce: u64, contract: Pubkey, operation_type: OperationType, data: Vec<u8>) -> Self {
+        OperationContent {
+            nonce,
+            contract,
+            network: String::from(SOLANA_NETWORK),
+            operation_type,
+            data,
+        }
+    }
+
+    pub fn hash(self) -> solana_program::keccak::Hash {
+        let mut data = Vec::new();
+        data.append(&mut amount_bytes(self.nonce));
+
+        data.append(&mut Vec::from(self.contract.as_ref()));
+
+        data.append(&mut Vec::from(self.network.as_bytes()));
+
+        data.push(self.operation_type.into());
+
+        data.extend(self.data);
+
         solana_program::keccak::hash(data.as_slice())
     }
 }
@@ -59,6 +119,15 @@ pub struct TransferData {
     pub symbol: Option<String>,
     pub uri: Option<String>,
     pub decimals: Option<u8>,
+    // Part of the amount paid to the withdrawal submitter
+    pub relayer_fee: Option<u64>,
+    // Withdrawal index in the bitmap withdraw mode
+    pub bitmap_index: Option<u64>,
+    // Bitmap shards belong to the bridge admin, so the indexed transfer is bound to it
+    pub bridge_admin: Option<Pubkey>,
+    pub compressed: bool,
+    // Royalties of the wrapped NFT from the version 2 signed metadata
+    pub royalties: Option<SignedRoyalties>,
 }
 
 impl TransferData {
@@ -71,6 +140,11 @@ impl TransferData {
             symbol: Some(symbol),
             uri: Some(uri),
             decimals: Some(decimals),
+            relayer_fee: None,
+            bitmap_index: None,
+            bridge_admin: None,
+            compressed: false,
+            royalties: None,
         }
     }
 
@@ -83,6 +157,11 @@ impl TransferData {
             symbol: Some(symbol),
             uri: Some(uri),
             decimals: None,
+            relayer_fee: None,
+            bitmap_index: None,
+            bridge_admin: None,
+            compressed: false,
+            royalties: None,
         }
     }
 
@@ -95,8 +174,34 @@ impl TransferData {
             symbol: None,
             uri: None,
             decimals: None,
+            relayer_fee: None,
+            bitmap_index: None,
+            bridge_admin: None,
+            compressed: false,
+            royalties: None,
         }
     }
+
+    pub fn with_relayer_fee(mut self, relayer_fee: Option<u64>) -> Self {
+        self.relayer_fee = relayer_fee;
+        self
+    }
+
+    pub fn with_bitmap_index(mut self, bitmap_index: Option<u64>, bridge_admin: &Pubkey) -> Self {
+        self.bitmap_index = bitmap_index;
+        self.bridge_admin = bitmap_index.map(|_| *bridge_admin);
+        self
+    }
+
+    pub fn with_compressed(mut self) -> Self {
+        self.compressed = true;
+        self
+    }
+
+    pub fn with_royalties(mut self, royalties: Option<SignedRoyalties>) -> Self {
+        self.royalties = royalties;
+        self
+    }
 }
 
 impl Data for TransferData {
@@ -131,6 +236,135 @@ impl Data for TransferData {
             data.push(val);
         }
 
+        if self.compressed {
+            data.append(&mut Vec::from(COMPRESSED_TAG));
+        }
+
+        if let Some(val) = &self.royalties {
+            data.append(&mut Vec::from(METADATA_V2_TAG));
+            data.append(&mut royalties_bytes(val.seller_fee_basis_points, &val.creators));
+        }
+
+        if let Some(val) = self.relayer_fee {
+            data.append(&mut Vec::from(RELAYER_FEE_TAG));
+            data.append(&mut amount_bytes(val));
+        }
+
+        if let Some(val) = self.bitmap_index {
+            data.append(&mut Vec::from(BITMAP_INDEX_TAG));
+            data.append(&mut amount_bytes(val));
+        }
+
+        if let Some(val) = self.bridge_admin {
+            data.append(&mut Vec::from(val.as_ref()));
+        }
+
+        data
+    }
+}
+
+// Royalty followed by the creators (address and share)
+pub fn royalties_bytes(seller_fee_basis_points: u16, creators: &[SignedCreator]) -> Vec<u8> {
+    let mut data = amount_bytes(seller_fee_basis_points as u64);
+    for creator in creators {
+        data.extend_from_slice(creator.address.as_ref());
+        data.push(creator.share);
+    }
+
+    data
+}
+
+pub struct MessageData {
+    // Sender address on the source network
+    pub sender: String,
+    pub payload: Vec<u8>,
+}
+
+impl MessageData {
+    pub fn new(sender: String, payload: Vec<u8>) -> Self {
+        MessageData {
+            sender,
+            payload,
+        }
+    }
+}
+
+impl Data for MessageData {
+    fn get_operation(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.append(&mut Vec::from(self.sender.as_bytes()));
+        data.append(&mut self.payload.clone());
         data
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{ft_transfer, MINT};
+
+    const ORIGIN: [u8; 32] = [2u8; 32];
+    const RECEIVER: [u8; 32] = [3u8; 32];
+    const PROGRAM_ID: [u8; 32] = [4u8; 32];
+
+    // Version 1 transfers without the optional fields keep the original encoding
+    #[test]
+    fn transfer_data_v1() {
+        let mut expected = Vec::from(MINT.as_slice());
+        expected.extend_from_slice(b"Token");
+        expected.extend_from_slice(b"https://example.com");
+        expected.extend(amount_bytes(1000));
+        expected.extend_from_slice(b"TKN");
+        expected.push(9);
+        assert_eq!(ft_transfer().get_operation(), expected);
+
+        assert_eq!(TransferData::new_native_transfer(1000).get_operation(), amount_bytes(1000));
+
+        let collection = [5u8; 32];
+        let mut expected = Vec::from(collection.as_slice());
+        expected.extend_from_slice(b"NFT");
+        expected.extend_from_slice(MINT.as_slice());
+        expected.extend_from_slice(b"https://example.com");
+        expected.extend_from_slice(b"N");
+        let nft = TransferData::new_nft_transfer(MINT, Some(collection), "NFT".to_string(), "N".to_string(), "https://example.com".to_string());
+        assert_eq!(nft.get_operation(), expected);
+    }
+
+    #[test]
+    fn transfer_content_v1_hash() {
+        let hash = Content::new(ORIGIN, RECEIVER, PROGRAM_ID, Box::new(ft_transfer())).hash();
+
+        let mut expected = ft_transfer().get_operation();
+        expected.extend_from_slice(ORIGIN.as_slice());
+        expected.extend_from_slice(SOLANA_NETWORK.as_bytes());
+        expected.extend_from_slice(RECEIVER.as_slice());
+        expected.extend_from_slice(PROGRAM_ID.as_slice());
+        assert_eq!(hash, solana_program::keccak::hash(expected.as_slice()));
+        assert_eq!(hash.to_string(), "AbAwhMVzRWCHgL6sspDx1EvDNuMMcAnQQcAciyRPAx6e");
+    }
+
+    #[test]
+    fn transfer_data_relayer_fee() {
+        let mut expected = ft_transfer().get_operation();
+        expected.extend_from_slice(RELAYER_FEE_TAG);
+        expected.extend(amount_bytes(10));
+        assert_eq!(ft_transfer().with_relayer_fee(Some(10)).get_operation(), expected);
+        assert_eq!(ft_transfer().with_relayer_fee(None).get_operation(), ft_transfer().get_operation());
+    }
+
+    #[test]
+    fn transfer_data_royalties() {
+        let nft = || TransferData::new_nft_transfer(MINT, None, "NFT".to_string(), "N".to_string(), "https://example.com".to_string());
+        let creators = vec![SignedCreator { address: Pubkey::new_unique(), share: 100 }];
+
+        let mut expected = nft().get_operation();
+        expected.extend_from_slice(METADATA_V2_TAG);
+        expected.extend(amount_bytes(500));
+        expected.extend_from_slice(creators[0].address.as_ref());
+        expected.push(100);
+
+        let royalties = SignedRoyalties { seller_fee_basis_points: 500, creators };
+        assert_eq!(nft().with_royalties(Some(royalties)).get_operation(), expected);
+        assert_eq!(nft().with_royalties(None).get_operation(), nft().get_operation());
+    }
+}
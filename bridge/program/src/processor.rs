@@ -1,40 +1,65 @@
 use std::cmp::max;
+use std::slice::Iter;
 
 use borsh::{
     BorshDeserialize, BorshSerialize,
 };
+use mpl_bubblegum::{
+    accounts::TreeConfig,
+    hash::{hash_creators, hash_metadata},
+    instructions::{Burn as BurnLeaf, BurnInstructionArgs as BurnLeafInstructionArgs, MintV1, MintV1InstructionArgs, Transfer as TransferLeaf, TransferInstructionArgs as TransferLeafInstructionArgs},
+    types::{Creator as LeafCreator, MetadataArgs, TokenProgramVersion, TokenStandard as LeafTokenStandard},
+};
 use mpl_token_metadata::{
-    instructions::{CreateMasterEditionV3, CreateMetadataAccountV3, VerifyCollection, CreateMetadataAccountV3InstructionArgs},
-    types::{DataV2, TokenStandard},
-    accounts::Metadata,
+    instructions::{CreateMasterEditionV3, CreateMasterEditionV3InstructionArgs, CreateMetadataAccountV3, VerifyCollection, CreateMetadataAccountV3InstructionArgs, TransferV1, TransferV1InstructionArgs, VerifySizedCollectionItem, UpdateMetadataAccountV2, UpdateMetadataAccountV2InstructionArgs},
+    types::{Collection, Creator, DataV2, TokenStandard},
+    accounts::{MasterEdition, Metadata},
 };
 use solana_program::{
     account_info::{AccountInfo, next_account_info},
-    entrypoint::ProgramResult, hash, msg,
-    program::{invoke, invoke_signed}, pubkey::Pubkey, secp256k1_recover::{SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH}, system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    entrypoint::ProgramResult, msg, program_error::ProgramError,
+    instruction::{AccountMeta, Instruction},
+    program::{invoke, invoke_signed}, pubkey::Pubkey, system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
     system_program as g_system_program,
 };
-use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
-use spl_token::{
-    instruction::{initialize_mint, mint_to, transfer},
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    instruction::{close_account, initialize_mint, mint_to, sync_native, transfer_checked},
     solana_program::program_pack::Pack,
-    state::Mint,
+    state::{Account, Mint},
 };
-use spl_token::instruction::burn;
+use spl_token_2022::instruction::burn_checked;
 
 use crate::{
     state::BridgeAdmin,
+    state::LegacyBridgeAdmin,
+    state::DepositLimit,
+    state::Deposit,
+    state::Message,
+    state::MessageReceipt,
+    state::NetworkRegistry,
+    state::OperationType,
+    state::TokenPolicy,
+    state::RateLimit,
+    state::SignedRoot,
     state::Withdraw,
+    state::WithdrawBitmap,
+    state::MetadataNonce,
+    state::WrappedAsset,
 };
-use crate::merkle::{Data, TransferData, Content};
+use crate::merkle::{TransferData, MessageData, Content, OperationContent, royalties_bytes};
 use solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
-use lib::merkle::{get_merkle_root};
-use lib::ecdsa::verify_ecdsa_signature;
-use lib::instructions::bridge::{BridgeInstruction, SignedMetadata};
+use lib::merkle::{amount_bytes, get_merkle_root};
+use lib::ecdsa::{verify_ecdsa_signature, EcdsaSignature, SignerSet};
+use lib::signers::SignerScheme;
+use lib::instructions::bridge::{BridgeInstruction, CompressedLeaf, PauseState, RootProof, SignedCreator, SignedMetadata, TargetCall, TokenPolicyMode, WithdrawMode, WithdrawTransfer, WITHDRAW_BITMAP_BITS};
 use lib::instructions::InstructionValidation;
 use lib::error::LibError;
-use crate::state::{BRIDGE_ADMIN_SIZE, WITHDRAW_SIZE};
+use lib::events::{DepositEvent, MessageEvent};
+use lib::networks::{Network, MAX_NETWORKS_COUNT};
+use crate::state::{BRIDGE_ADMIN_SIZE, LEGACY_BRIDGE_ADMIN_SIZE, DEPOSIT_SIZE, MESSAGE_SIZE, MESSAGE_RECEIPT_SIZE, NETWORK_REGISTRY_SIZE, RATE_LIMIT_SIZE, DEPOSIT_LIMIT_SIZE, TOKEN_POLICY_SIZE, SIGNED_ROOT_SIZE, WITHDRAW_BITMAP_SIZE, WITHDRAW_SIZE, LEGACY_WITHDRAW_SIZE, METADATA_NONCE_SIZE, WRAPPED_ASSET_SIZE};
 
 pub fn process_instruction<'a>(
     program_id: &'a Pubkey,
@@ -45,44 +70,55 @@ pub fn process_instruction<'a>(
     match instruction {
         BridgeInstruction::InitializeAdmin(args) => {
             msg!("Instruction: Create Bridge Admin");
-            process_init_admin(program_id, accounts, args.seeds, args.public_key, args.commission_program)
+            args.validate()?;
+            process_init_admin(program_id, accounts, args.seeds, args.signers, args.commission_program)
         }
         BridgeInstruction::TransferOwnership(args) => {
             msg!("Instruction: Transfer Bridge Admin ownership");
-            process_transfer_ownership(program_id, accounts, args.seeds, args.new_public_key, args.signature, args.recovery_id)
+            args.validate()?;
+            process_transfer_ownership(program_id, accounts, args.seeds, args.new_signers, args.signatures)
         }
         BridgeInstruction::DepositNative(args) => {
             msg!("Instruction: Deposit SOL");
             args.validate()?;
-            process_deposit_native(program_id, accounts, args.seeds, args.network_to, args.receiver_address, args.amount)
+            process_deposit_native(program_id, accounts, args.seeds, args.network_to, args.receiver_address, args.amount, args.bundle_data, args.bundle_seed, args.wrapped_sol)
         }
         BridgeInstruction::DepositFT(args) => {
             msg!("Instruction: Deposit FT");
             args.validate()?;
-            process_deposit_ft(program_id, accounts, args.seeds, args.network_to, args.receiver_address, args.amount, args.token_seed)
+            process_deposit_ft(program_id, accounts, args.seeds, args.network_to, args.receiver_address, args.amount, args.token_seed, args.bundle_data, args.bundle_seed)
         }
         BridgeInstruction::DepositNFT(args) => {
             msg!("Instruction: Deposit NFT");
             args.validate()?;
-            process_deposit_nft(program_id, accounts, args.seeds, args.network_to, args.receiver_address, args.token_seed)
+            process_deposit_nft(program_id, accounts, args.seeds, args.network_to, args.receiver_address, args.token_seed, args.bundle_data, args.bundle_seed)
         }
 
         BridgeInstruction::WithdrawNative(args) => {
             msg!("Instruction: Withdraw SOL");
             args.validate()?;
-            process_withdraw_native(program_id, accounts, args.seeds, args.signature, args.recovery_id, args.path, args.origin, args.amount)
+            process_withdraw_native(program_id, &mut accounts.iter(), &mut RootVerifier::new(program_id, accounts, args.proof), args.seeds, args.path, args.origin, args.amount, args.relayer_fee, args.bitmap_index, args.wrapped_sol, None)
         }
 
         BridgeInstruction::WithdrawFT(args) => {
             msg!("Instruction: Withdraw FT");
             args.validate()?;
-            process_withdraw_ft(program_id, accounts, args.seeds, args.signature, args.recovery_id, args.path, args.origin, args.amount, args.token_seed, args.signed_meta)
+            if args.wrapped_sol {
+                return Err(LibError::WrongTokenType.into());
+            }
+            process_withdraw_ft(program_id, &mut accounts.iter(), &mut RootVerifier::new(program_id, accounts, args.proof), args.seeds, args.path, args.origin, args.amount, args.token_seed, args.signed_meta, args.relayer_fee, args.bitmap_index, None)
         }
 
         BridgeInstruction::WithdrawNFT(args) => {
             msg!("Instruction: Withdraw NFT");
             args.validate()?;
-            process_withdraw_nft(program_id, accounts, args.seeds, args.signature, args.recovery_id, args.path, args.origin, args.token_seed, args.signed_meta)
+            if args.relayer_fee.is_some() {
+                return Err(LibError::WrongRelayerFee.into());
+            }
+            if args.wrapped_sol {
+                return Err(LibError::WrongTokenType.into());
+            }
+            process_withdraw_nft(program_id, &mut accounts.iter(), &mut RootVerifier::new(program_id, accounts, args.proof), args.seeds, args.path, args.origin, args.token_seed, args.signed_meta, args.bitmap_index, None)
         }
 
         BridgeInstruction::MintCollection(args) => {
@@ -90,6 +126,120 @@ pub fn process_instruction<'a>(
             args.validate()?;
             process_create_collection(program_id, accounts, args.seeds, args.data, args.token_seed)
         }
+
+        BridgeInstruction::WithdrawAndCall(args) => {
+            msg!("Instruction: Withdraw and call");
+            args.validate()?;
+            let w = args.withdraw;
+            let account_info_iter = &mut accounts.iter();
+            let verifier = &mut RootVerifier::new(program_id, accounts, w.proof);
+            match args.token_type {
+                lib::TokenType::Native => process_withdraw_native(program_id, account_info_iter, verifier, w.seeds, w.path, w.origin, w.amount, w.relayer_fee, w.bitmap_index, w.wrapped_sol, Some(args.call)),
+                lib::TokenType::FT => process_withdraw_ft(program_id, account_info_iter, verifier, w.seeds, w.path, w.origin, w.amount, w.token_seed, w.signed_meta, w.relayer_fee, w.bitmap_index, Some(args.call)),
+                lib::TokenType::NFT => process_withdraw_nft(program_id, account_info_iter, verifier, w.seeds, w.path, w.origin, w.token_seed, w.signed_meta, w.bitmap_index, Some(args.call)),
+                lib::TokenType::Message | lib::TokenType::CNFT => Err(LibError::WrongTokenType.into()),
+            }
+        }
+
+        BridgeInstruction::SendMessage(args) => {
+            msg!("Instruction: Send message");
+            args.validate()?;
+            process_send_message(program_id, accounts, args.seeds, args.network_to, args.target_address, args.payload)
+        }
+
+        BridgeInstruction::ReceiveMessage(args) => {
+            msg!("Instruction: Receive message");
+            args.validate()?;
+            process_receive_message(program_id, accounts, args.seeds, args.signatures, args.path, args.origin, args.sender, args.payload)
+        }
+
+        BridgeInstruction::InitializeNetworkRegistry(args) => {
+            msg!("Instruction: Create Network Registry");
+            process_init_network_registry(program_id, accounts, args.seeds, args.signatures, args.path)
+        }
+
+        BridgeInstruction::AddNetwork(args) => {
+            msg!("Instruction: Add network");
+            args.validate()?;
+            process_add_network(program_id, accounts, args.seeds, args.signatures, args.path, args.network)
+        }
+
+        BridgeInstruction::RemoveNetwork(args) => {
+            msg!("Instruction: Remove network");
+            args.validate()?;
+            process_remove_network(program_id, accounts, args.seeds, args.signatures, args.path, args.network)
+        }
+
+        BridgeInstruction::SetPause(args) => {
+            msg!("Instruction: Set pause");
+            process_set_pause(program_id, accounts, args.seeds, args.signatures, args.path, args.pause)
+        }
+
+        BridgeInstruction::SetRateLimit(args) => {
+            msg!("Instruction: Set rate limit");
+            process_set_rate_limit(program_id, accounts, args.seeds, args.signatures, args.path, args.mint, args.capacity, args.refill_per_second)
+        }
+
+        BridgeInstruction::SetDepositLimit(args) => {
+            msg!("Instruction: Set deposit limit");
+            args.validate()?;
+            process_set_deposit_limit(program_id, accounts, args.seeds, args.signatures, args.path, args.mint, args.min_amount, args.max_amount)
+        }
+
+        BridgeInstruction::SetTokenPolicy(args) => {
+            msg!("Instruction: Set token policy");
+            args.validate()?;
+            process_set_token_policy(program_id, accounts, args.seeds, args.signatures, args.path, args.mode, args.mints)
+        }
+
+        BridgeInstruction::WithdrawBatch(args) => {
+            msg!("Instruction: Withdraw batch");
+            args.validate()?;
+            process_withdraw_batch(program_id, accounts, args.seeds, args.proof, args.transfers)
+        }
+
+        BridgeInstruction::SubmitRoot(args) => {
+            msg!("Instruction: Submit root");
+            process_submit_root(program_id, accounts, args.seeds, args.root, args.signatures)
+        }
+
+        BridgeInstruction::SetWithdrawMode(args) => {
+            msg!("Instruction: Set withdraw mode");
+            process_set_withdraw_mode(program_id, accounts, args.seeds, args.signatures, args.path, args.mode)
+        }
+
+        BridgeInstruction::CloseWithdraw(args) => {
+            msg!("Instruction: Close withdraw");
+            process_close_withdraw(program_id, accounts, args.seeds, args.signatures, args.path, args.origin, args.bitmap_index)
+        }
+
+        BridgeInstruction::DepositCNFT(args) => {
+            msg!("Instruction: Deposit compressed NFT");
+            args.validate()?;
+            process_deposit_cnft(program_id, accounts, args.seeds, args.network_to, args.receiver_address, args.leaf, args.token_seed, args.bundle_data, args.bundle_seed)
+        }
+
+        BridgeInstruction::WithdrawCNFT(args) => {
+            msg!("Instruction: Withdraw compressed NFT");
+            args.validate()?;
+            process_withdraw_cnft(program_id, accounts, args.seeds, args.proof, args.path, args.origin, args.leaf, args.token_seed, args.signed_meta, args.bitmap_index)
+        }
+
+        BridgeInstruction::UpdateWrappedMetadata(args) => {
+            msg!("Instruction: Update wrapped metadata");
+            args.validate()?;
+            process_update_wrapped_metadata(program_id, accounts, args.seeds, args.signatures, args.path, args.token_seed, args.name, args.symbol, args.uri, args.seller_fee_basis_points, args.creators)
+        }
+
+        BridgeInstruction::MigrateSolVault(args) => {
+            msg!("Instruction: Migrate SOL vault");
+            process_migrate_sol_vault(program_id, accounts, args.seeds, args.signatures, args.path)
+        }
+
+        BridgeInstruction::MigrateAdmin(args) => {
+            msg!("Instruction: Migrate admin");
+            process_migrate_admin(program_id, accounts, args.seeds, args.signature)
+        }
     }
 }
 
@@ -97,7 +247,7 @@ pub fn process_init_admin<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
     seeds: [u8; 32],
-    public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
+    signers: SignerScheme,
     commission_program: Pubkey,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -106,12 +256,15 @@ pub fn process_init_admin<'a>(
     let fee_payer_info = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
+    let sol_vault_info = next_account_info(account_info_iter)?;
 
     let bridge_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
     if bridge_key != *bridge_admin_info.key {
         return Err(LibError::WrongSeeds.into());
     }
 
+    verify_sol_vault(program_id, &bridge_key, sol_vault_info)?;
+
     lib::call_create_account(
         fee_payer_info,
         bridge_admin_info,
@@ -127,10 +280,29 @@ pub fn process_init_admin<'a>(
         return Err(LibError::AlreadyInUse.into());
     }
 
-    bridge_admin.public_key = public_key;
+    bridge_admin.signers = signers;
+    bridge_admin.deposit_nonce = 0;
+    bridge_admin.message_nonce = 0;
+    bridge_admin.pause = PauseState::default();
+    bridge_admin.admin_nonce = 0;
+    bridge_admin.withdraw_mode = WithdrawMode::default();
     bridge_admin.is_initialized = true;
     bridge_admin.commission_program = commission_program;
     bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+
+    // Funding the vault rent-exempt reserve, so deposits of any amount can be accepted
+    let vault_rent = Rent::from_account_info(rent_info)?.minimum_balance(0).saturating_sub(sol_vault_info.lamports());
+    if vault_rent > 0 {
+        msg!("Funding SOL vault");
+        invoke(
+            &solana_program::system_instruction::transfer(fee_payer_info.key, sol_vault_info.key, vault_rent),
+            &[
+                fee_payer_info.clone(),
+                sol_vault_info.clone(),
+            ],
+        )?;
+    }
+
     Ok(())
 }
 
@@ -138,12 +310,12 @@ pub fn process_transfer_ownership<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
     seeds: [u8; 32],
-    new_public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    recovery_id: u8,
+    new_signers: SignerScheme,
+    signatures: Vec<EcdsaSignature>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let bridge_admin_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
 
     let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
     if bridge_admin_key != *bridge_admin_info.key {
@@ -155,10 +327,9 @@ pub fn process_transfer_ownership<'a>(
         return Err(LibError::NotInitialized.into());
     }
 
+    bridge_admin.signers.verify(new_signers.hash()?.as_slice(), &signatures, instruction_sysvar_info)?;
 
-    verify_ecdsa_signature(solana_program::keccak::hash(new_public_key.as_slice()).as_ref(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
-
-    bridge_admin.public_key = new_public_key;
+    bridge_admin.signers = new_signers;
     bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
     Ok(())
 }
@@ -171,6 +342,9 @@ pub fn process_deposit_native<'a>(
     network: String,
     receiver: String,
     amount: u64,
+    bundle_data: Option<Vec<u8>>,
+    bundle_seed: Option<[u8; 32]>,
+    wrapped_sol: bool,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
@@ -179,22 +353,49 @@ pub fn process_deposit_native<'a>(
     let system_program = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
     let sysvar_info = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+    let deposit_limit_info = next_account_info(account_info_iter)?;
+    let sol_vault_info = next_account_info(account_info_iter)?;
 
     let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
     if *bridge_admin_info.key != bridge_admin_key {
         return Err(LibError::WrongSeeds.into());
     }
 
-    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
     if !bridge_admin.is_initialized {
         return Err(LibError::NotInitialized.into());
     }
 
+    bridge_admin.pause.check_deposit(&lib::TokenType::Native)?;
+
+    verify_sol_vault_migrated(program_id, &bridge_admin_key, sol_vault_info)?;
+
+    verify_network(program_id, bridge_admin_info, network_registry_info, &network, &receiver)?;
+
+    verify_deposit_limit(program_id, bridge_admin_info, deposit_limit_info, None, amount)?;
+
     verify_commission_charged( bridge_admin_info, sysvar_info, &bridge_admin, lib::TokenType::Native, amount)?;
 
+    if wrapped_sol {
+        let owner_associated_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        verify_native_associated(owner_info, owner_associated_info, token_program)?;
+
+        // Closing the wSOL account returns all its lamports to the owner
+        msg!("Unwrapping SOL");
+        call_close_account(
+            token_program,
+            owner_associated_info,
+            owner_info,
+            owner_info,
+        )?;
+    }
+
     let transfer_tokens_instruction = solana_program::system_instruction::transfer(
         owner_info.key,
-        bridge_admin_info.key,
+        sol_vault_info.key,
         amount,
     );
 
@@ -203,11 +404,39 @@ pub fn process_deposit_native<'a>(
         &transfer_tokens_instruction,
         &[
             owner_info.clone(),
-            bridge_admin_info.clone(),
+            sol_vault_info.clone(),
         ],
     )?;
 
-    Ok(())
+    let deposit_id = next_deposit_id(bridge_admin_info, &mut bridge_admin)?;
+    msg!("Deposit id: {}", deposit_id);
+
+    let event = DepositEvent {
+        deposit_id,
+        token_type: lib::TokenType::Native,
+        mint: None,
+        amount,
+        network_to: network,
+        receiver_address: receiver,
+        sender: *owner_info.key,
+        bundle_salt: bundle_seed.map(|seed| lib::get_bundle_salt(&seed, owner_info.key)),
+        bundle_data,
+        bundle_seed,
+    };
+
+    if let Ok(deposit_info) = next_account_info(account_info_iter) {
+        create_deposit_record(
+            program_id,
+            bridge_admin_info,
+            deposit_info,
+            owner_info,
+            rent_info,
+            system_program,
+            &event,
+        )?;
+    }
+
+    event.emit()
 }
 
 pub fn process_deposit_ft<'a>(
@@ -218,6 +447,8 @@ pub fn process_deposit_ft<'a>(
     receiver: String,
     amount: u64,
     token_seed: Option<[u8; 32]>,
+    bundle_data: Option<Vec<u8>>,
+    bundle_seed: Option<[u8; 32]>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
@@ -232,21 +463,31 @@ pub fn process_deposit_ft<'a>(
     let rent_info = next_account_info(account_info_iter)?;
     let sysvar_info = next_account_info(account_info_iter)?;
     let _associated_program = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+    let deposit_limit_info = next_account_info(account_info_iter)?;
+    let token_policy_info = next_account_info(account_info_iter)?;
 
     let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
     if *bridge_admin_info.key != bridge_admin_key {
         return Err(LibError::WrongSeeds.into());
     }
 
-    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
     if !bridge_admin.is_initialized {
         return Err(LibError::NotInitialized.into());
     }
 
-    verify_commission_charged(bridge_admin_info, sysvar_info, &bridge_admin, lib::TokenType::FT, amount)?;
+    bridge_admin.pause.check_deposit(&lib::TokenType::FT)?;
+
+    verify_network(program_id, bridge_admin_info, network_registry_info, &network, &receiver)?;
+
+    verify_token_policy(program_id, bridge_admin_info, token_policy_info, mint_info.key)?;
+
+    verify_token_program(token_program)?;
+    let mint = load_mint(token_program, mint_info)?;
 
     if *bridge_associated_info.key !=
-        get_associated_token_address(&bridge_admin_key, mint_info.key) {
+        get_associated_token_address_with_program_id(&bridge_admin_key, mint_info.key, token_program.key) {
         return Err(LibError::WrongTokenAccount.into());
     }
 
@@ -263,7 +504,8 @@ pub fn process_deposit_ft<'a>(
         )?;
     }
 
-
+    // Tokens with the transfer fee extension are credited with the net received amount
+    let mut received = amount;
     if let Some(token_seed) = token_seed {
         let (mint_key, _) = Pubkey::find_program_address(&[token_seed.as_slice()], program_id);
         if mint_key != *mint_info.key {
@@ -272,23 +514,65 @@ pub fn process_deposit_ft<'a>(
 
         msg!("Burning token");
         call_burn_token(
+            token_program,
             owner_associated_info,
             mint_info,
             owner_info,
             amount,
+            mint.decimals,
         )?;
     } else {
+        let balance = token_account_amount(bridge_associated_info)?;
+
         msg!("Transferring token");
         call_transfer_token(
+            token_program,
             owner_associated_info,
+            mint_info,
             bridge_associated_info,
             owner_info,
             amount,
+            mint.decimals,
             &[],
         )?;
+
+        received = token_account_amount(bridge_associated_info)?.checked_sub(balance).ok_or(ProgramError::from(LibError::WrongBalance))?;
     }
 
-    Ok(())
+    // Limits and commission apply to the amount credited to the bridge
+    verify_deposit_limit(program_id, bridge_admin_info, deposit_limit_info, Some(mint_info.key), received)?;
+
+    verify_commission_charged(bridge_admin_info, sysvar_info, &bridge_admin, lib::TokenType::FT, received)?;
+
+    let deposit_id = next_deposit_id(bridge_admin_info, &mut bridge_admin)?;
+    msg!("Deposit id: {}", deposit_id);
+
+    let event = DepositEvent {
+        deposit_id,
+        token_type: lib::TokenType::FT,
+        mint: Some(*mint_info.key),
+        amount: received,
+        network_to: network,
+        receiver_address: receiver,
+        sender: *owner_info.key,
+        bundle_salt: bundle_seed.map(|seed| lib::get_bundle_salt(&seed, owner_info.key)),
+        bundle_data,
+        bundle_seed,
+    };
+
+    if let Ok(deposit_info) = next_account_info(account_info_iter) {
+        create_deposit_record(
+            program_id,
+            bridge_admin_info,
+            deposit_info,
+            owner_info,
+            rent_info,
+            system_program,
+            &event,
+        )?;
+    }
+
+    event.emit()
 }
 
 pub fn process_deposit_nft<'a>(
@@ -298,6 +582,8 @@ pub fn process_deposit_nft<'a>(
     network: String,
     receiver: String,
     token_seed: Option<[u8; 32]>,
+    bundle_data: Option<Vec<u8>>,
+    bundle_seed: Option<[u8; 32]>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
@@ -311,22 +597,50 @@ pub fn process_deposit_nft<'a>(
     let system_program = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
     let sysvar_info = next_account_info(account_info_iter)?;
-    let _associated_program = next_account_info(account_info_iter)?;
+    let associated_program = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+    let token_policy_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
 
     let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
     if *bridge_admin_info.key != bridge_admin_key {
         return Err(LibError::WrongSeeds.into());
     }
 
-    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
     if !bridge_admin.is_initialized {
         return Err(LibError::NotInitialized.into());
     }
 
+    bridge_admin.pause.check_deposit(&lib::TokenType::NFT)?;
+
+    if *metadata_info.key != Metadata::find_pda(mint_info.key).0 {
+        return Err(LibError::WrongMetadataAccount.into());
+    }
+
+    // Tokens without Metaplex metadata are deposited as standard NFTs
+    let metadata = if metadata_info.data_is_empty() {
+        None
+    } else {
+        Some(Metadata::from_bytes(&metadata_info.data.borrow())?)
+    };
+
+    let programmable = match &metadata {
+        Some(metadata) => ProgrammableAccounts::next(account_info_iter, metadata)?,
+        None => None,
+    };
+
+    verify_network(program_id, bridge_admin_info, network_registry_info, &network, &receiver)?;
+
+    verify_token_policy(program_id, bridge_admin_info, token_policy_info, mint_info.key)?;
+
     verify_commission_charged( bridge_admin_info, sysvar_info, &bridge_admin, lib::TokenType::NFT, 1)?;
 
+    verify_token_program(token_program)?;
+    let mint = load_mint(token_program, mint_info)?;
+
     if *bridge_associated_info.key !=
-        get_associated_token_address(&bridge_admin_key, mint_info.key) {
+        get_associated_token_address_with_program_id(&bridge_admin_key, mint_info.key, token_program.key) {
         return Err(LibError::WrongTokenAccount.into());
     }
 
@@ -349,140 +663,253 @@ pub fn process_deposit_nft<'a>(
             return Err(LibError::WrongTokenSeed.into());
         }
 
-        msg!("Burning token");
-        call_burn_token(
-            owner_associated_info,
+    }
+
+    // Wrapped NFTs are locked as well: the mint authority moves to the master edition on the first withdrawal,
+    // so the next withdrawal of the same token seed transfers the locked token instead of minting it
+    if let Some(programmable) = programmable {
+        msg!("Transferring programmable token");
+        programmable.call_transfer(
+            token_program,
+            associated_program,
+            system_program,
             mint_info,
+            metadata_info,
+            owner_associated_info,
             owner_info,
-            1,
+            programmable.owner_token_record,
+            bridge_associated_info,
+            bridge_admin_info,
+            programmable.bridge_token_record,
+            owner_info,
+            &[],
         )?;
     } else {
         msg!("Transferring token");
         call_transfer_token(
+            token_program,
             owner_associated_info,
+            mint_info,
             bridge_associated_info,
             owner_info,
             1,
+            mint.decimals,
             &[],
         )?;
     }
 
-    Ok(())
+    let deposit_id = next_deposit_id(bridge_admin_info, &mut bridge_admin)?;
+    msg!("Deposit id: {}", deposit_id);
+
+    let event = DepositEvent {
+        deposit_id,
+        token_type: lib::TokenType::NFT,
+        mint: Some(*mint_info.key),
+        amount: 1,
+        network_to: network,
+        receiver_address: receiver,
+        sender: *owner_info.key,
+        bundle_salt: bundle_seed.map(|seed| lib::get_bundle_salt(&seed, owner_info.key)),
+        bundle_data,
+        bundle_seed,
+    };
+
+    if let Ok(deposit_info) = next_account_info(account_info_iter) {
+        create_deposit_record(
+            program_id,
+            bridge_admin_info,
+            deposit_info,
+            owner_info,
+            rent_info,
+            system_program,
+            &event,
+        )?;
+    }
+
+    event.emit()
 }
 
-pub fn process_withdraw_native<'a>(
+pub fn process_deposit_cnft<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
     seeds: [u8; 32],
-    signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    recovery_id: u8,
-    path: Vec<[u8; 32]>,
-    origin: [u8; 32],
-    amount: u64,
+    network: String,
+    receiver: String,
+    leaf: CompressedLeaf,
+    token_seed: Option<[u8; 32]>,
+    bundle_data: Option<Vec<u8>>,
+    bundle_seed: Option<[u8; 32]>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
     let bridge_admin_info = next_account_info(account_info_iter)?;
     let owner_info = next_account_info(account_info_iter)?;
-    let withdraw_info = next_account_info(account_info_iter)?;
+    let delegate_info = next_account_info(account_info_iter)?;
+    let tree_config_info = next_account_info(account_info_iter)?;
+    let merkle_tree_info = next_account_info(account_info_iter)?;
 
+    let bubblegum_program = next_account_info(account_info_iter)?;
+    let log_wrapper = next_account_info(account_info_iter)?;
+    let compression_program = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
+    let sysvar_info = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+    let token_policy_info = next_account_info(account_info_iter)?;
+    let wrapped_asset_info = match token_seed {
+        Some(_) => Some(next_account_info(account_info_iter)?),
+        None => None,
+    };
 
-    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
     if *bridge_admin_info.key != bridge_admin_key {
         return Err(LibError::WrongSeeds.into());
     }
 
-    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
     if !bridge_admin.is_initialized {
         return Err(LibError::NotInitialized.into());
     }
 
-    let content = Content::new(
-        origin,
-        owner_info.key.to_bytes(),
-        program_id.to_bytes(),
-        Box::new(
-            TransferData::new_native_transfer(
-                amount,
-            ),
-        ),
-    );
-    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.pause.check_deposit(&lib::TokenType::CNFT)?;
 
-    verify_ecdsa_signature(root.as_slice(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
+    let leaf_asset_id = mpl_bubblegum::utils::get_asset_id(merkle_tree_info.key, leaf.nonce);
 
-    // TODO check rent
-    if **bridge_admin_info.try_borrow_lamports()? < amount {
-        return Err(LibError::WrongBalance.into());
-    }
+    // Wrapped asset is reported by the token seed as on withdrawal, its leaf should be the last minted one
+    let asset_id = match (token_seed, wrapped_asset_info) {
+        (Some(token_seed), Some(wrapped_asset_info)) => {
+            verify_wrapped_asset(program_id, &bridge_admin_key, wrapped_asset_info, token_seed)?;
 
-    let (withdraw_key, bump_seed) = Pubkey::find_program_address(&[origin.as_slice()], program_id);
-    if withdraw_key != *withdraw_info.key {
-        return Err(LibError::WrongNonce.into());
-    }
+            let wrapped_asset: WrappedAsset = BorshDeserialize::deserialize(&mut wrapped_asset_info.data.borrow().as_ref())?;
+            if !wrapped_asset.is_initialized || wrapped_asset.asset_id != Some(leaf_asset_id) {
+                return Err(LibError::WrongTokenSeed.into());
+            }
 
-    // Need to do that before transferring SOls
-    msg!("Creating withdraw account");
-    lib::call_create_account(
-        owner_info,
-        withdraw_info,
-        rent_info,
-        system_program,
-        WITHDRAW_SIZE,
-        program_id,
-        &[origin.as_slice(), &[bump_seed]],
-    )?;
+            Pubkey::find_program_address(&[token_seed.as_slice()], program_id).0
+        }
+        _ => leaf_asset_id,
+    };
 
-    msg!("Transferring token");
-    **bridge_admin_info.try_borrow_mut_lamports()? -= amount;
-    **owner_info.try_borrow_mut_lamports()? += amount;
+    verify_network(program_id, bridge_admin_info, network_registry_info, &network, &receiver)?;
 
-    msg!("Initializing withdraw account");
-    let mut withdraw: Withdraw = BorshDeserialize::deserialize(&mut withdraw_info.data.borrow_mut().as_ref())?;
-    if withdraw.is_initialized {
-        return Err(LibError::AlreadyInUse.into());
+    verify_token_policy(program_id, bridge_admin_info, token_policy_info, &asset_id)?;
+
+    verify_commission_charged(bridge_admin_info, sysvar_info, &bridge_admin, lib::TokenType::CNFT, 1)?;
+
+    // Optional deposit account precedes the proof nodes and is recognized by the address
+    let (deposit_key, _) = lib::instructions::bridge::get_deposit_address(program_id, &bridge_admin_key, bridge_admin.deposit_nonce);
+    let mut proof = account_info_iter.as_slice();
+    let deposit_info = match proof.first() {
+        Some(info) if *info.key == deposit_key => {
+            proof = &proof[1..];
+            Some(info)
+        }
+        _ => None,
+    };
+
+    if let Some(wrapped_asset_info) = wrapped_asset_info {
+        msg!("Burning wrapped leaf");
+        call_burn_leaf(
+            bubblegum_program,
+            tree_config_info,
+            owner_info,
+            delegate_info,
+            merkle_tree_info,
+            log_wrapper,
+            compression_program,
+            system_program,
+            &leaf,
+            proof,
+        )?;
+
+        WrappedAsset {
+            asset_id: None,
+            is_initialized: true,
+        }.serialize(&mut *wrapped_asset_info.data.borrow_mut())?;
+    } else {
+        msg!("Transferring leaf");
+        call_transfer_leaf(
+            bubblegum_program,
+            tree_config_info,
+            owner_info,
+            delegate_info,
+            bridge_admin_info,
+            merkle_tree_info,
+            log_wrapper,
+            compression_program,
+            system_program,
+            &leaf,
+            proof,
+            &[],
+        )?;
     }
 
-    withdraw.is_initialized = true;
-    withdraw.token_type = lib::TokenType::Native;
-    withdraw.origin = origin;
-    withdraw.mint = Option::None;
-    withdraw.amount = amount;
-    withdraw.receiver_address = *owner_info.key;
-    withdraw.serialize(&mut *withdraw_info.data.borrow_mut())?;
-    msg!("Withdraw account created");
-    Ok(())
+    let deposit_id = next_deposit_id(bridge_admin_info, &mut bridge_admin)?;
+    msg!("Deposit id: {}", deposit_id);
+
+    let event = DepositEvent {
+        deposit_id,
+        token_type: lib::TokenType::CNFT,
+        mint: Some(asset_id),
+        amount: 1,
+        network_to: network,
+        receiver_address: receiver,
+        sender: *owner_info.key,
+        bundle_salt: bundle_seed.map(|seed| lib::get_bundle_salt(&seed, owner_info.key)),
+        bundle_data,
+        bundle_seed,
+    };
+
+    if let Some(deposit_info) = deposit_info {
+        create_deposit_record(
+            program_id,
+            bridge_admin_info,
+            deposit_info,
+            owner_info,
+            rent_info,
+            system_program,
+            &event,
+        )?;
+    }
+
+    event.emit()
 }
 
-pub fn process_withdraw_ft<'a>(
+pub fn process_withdraw_native<'a>(
     program_id: &'a Pubkey,
-    accounts: &'a [AccountInfo<'a>],
+    account_info_iter: &mut Iter<'a, AccountInfo<'a>>,
+    verifier: &mut RootVerifier<'a>,
     seeds: [u8; 32],
-    signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    recovery_id: u8,
     path: Vec<[u8; 32]>,
     origin: [u8; 32],
     amount: u64,
-    token_seed: Option<[u8; 32]>,
-    signed_meta: Option<SignedMetadata>,
+    relayer_fee: Option<u64>,
+    bitmap_index: Option<u64>,
+    wrapped_sol: bool,
+    call: Option<TargetCall>,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-
     let bridge_admin_info = next_account_info(account_info_iter)?;
-    let mint_info = next_account_info(account_info_iter)?;
-    let metadata_info = next_account_info(account_info_iter)?;
     let owner_info = next_account_info(account_info_iter)?;
-    let owner_associated_info = next_account_info(account_info_iter)?;
-    let bridge_associated_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
     let withdraw_info = next_account_info(account_info_iter)?;
 
-    let token_program = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
-    let _metadata_program = next_account_info(account_info_iter)?;
-    let _associated_program = next_account_info(account_info_iter)?;
+    let rate_limit_info = next_account_info(account_info_iter)?;
+    let sol_vault_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    // SOL is delivered into the owner wSOL account: associated account, native mint, token and associated programs
+    let wrapped_accounts = if wrapped_sol {
+        Some((
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+        ))
+    } else {
+        None
+    };
 
     let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
     if *bridge_admin_info.key != bridge_admin_key {
@@ -494,28 +921,174 @@ pub fn process_withdraw_ft<'a>(
         return Err(LibError::NotInitialized.into());
     }
 
-    if *metadata_info.key != Metadata::find_pda(mint_info.key).0 {
-        return Err(LibError::WrongMetadataAccount.into());
+    bridge_admin.pause.check_withdrawal(&lib::TokenType::Native)?;
+
+    if !fee_payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if let Some(token_seed) = token_seed {
-        try_mint_token_with_meta(
-            program_id,
-            bridge_admin_info,
-            token_seed,
+    let vault_bump = verify_sol_vault_migrated(program_id, &bridge_admin_key, sol_vault_info)?;
+
+    let content = Content::new(
+        origin,
+        owner_info.key.to_bytes(),
+        program_id.to_bytes(),
+        Box::new(
+            TransferData::new_native_transfer(
+                amount,
+            ).with_relayer_fee(relayer_fee).with_bitmap_index(bitmap_index, &bridge_admin_key),
+        ),
+    ).with_call(call.clone());
+    let root = get_merkle_root(content.hash(), &path)?;
+
+    verifier.verify(bridge_admin_info, &bridge_admin, instruction_sysvar_info, root)?;
+
+    consume_rate_limit(program_id, bridge_admin_info, rate_limit_info, None, amount)?;
+
+    // The vault keeps its rent-exempt reserve
+    let reserve = Rent::get()?.minimum_balance(0);
+    if sol_vault_info.lamports().saturating_sub(reserve) < amount {
+        return Err(LibError::WrongBalance.into());
+    }
+
+    let vault_seeds: &[&[u8]] = &[lib::SOL_VAULT_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), &[vault_bump]];
+
+    // Need to do that before transferring SOls
+    record_withdraw(
+        program_id,
+        &bridge_admin,
+        &bridge_admin_key,
+        withdraw_info,
+        fee_payer_info,
+        rent_info,
+        system_program,
+        origin,
+        bitmap_index,
+        lib::TokenType::Native,
+        None,
+        amount,
+        *owner_info.key,
+    )?;
+
+    let fee = relayer_fee.unwrap_or(0);
+
+    if let Some((owner_associated_info, native_mint_info, token_program, _)) = wrapped_accounts {
+        verify_native_associated(owner_info, owner_associated_info, token_program)?;
+
+        if owner_associated_info.data_is_empty() {
+            msg!("Create owner wSOL associated account");
+            lib::call_create_associated_account(
+                fee_payer_info,
+                owner_info,
+                native_mint_info,
+                owner_associated_info,
+                rent_info,
+                system_program,
+                token_program,
+            )?;
+        }
+
+        msg!("Transferring wrapped token");
+        call_transfer_sol(system_program, sol_vault_info, owner_associated_info, amount - fee, vault_seeds)?;
+        call_sync_native(token_program, owner_associated_info)?;
+    } else {
+        msg!("Transferring token");
+        call_transfer_sol(system_program, sol_vault_info, owner_info, amount - fee, vault_seeds)?;
+    }
+
+    if fee > 0 {
+        msg!("Transferring relayer fee");
+        call_transfer_sol(system_program, sol_vault_info, fee_payer_info, fee, vault_seeds)?;
+    }
+
+    if let Some(call) = call {
+        call_target_program(program_id, bridge_admin_info, account_info_iter, call)?;
+    }
+
+    Ok(())
+}
+
+pub fn process_withdraw_ft<'a>(
+    program_id: &'a Pubkey,
+    account_info_iter: &mut Iter<'a, AccountInfo<'a>>,
+    verifier: &mut RootVerifier<'a>,
+    seeds: [u8; 32],
+    path: Vec<[u8; 32]>,
+    origin: [u8; 32],
+    amount: u64,
+    token_seed: Option<[u8; 32]>,
+    signed_meta: Option<SignedMetadata>,
+    relayer_fee: Option<u64>,
+    bitmap_index: Option<u64>,
+    call: Option<TargetCall>,
+) -> ProgramResult {
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let fee_payer_associated_info = next_account_info(account_info_iter)?;
+    let owner_associated_info = next_account_info(account_info_iter)?;
+    let bridge_associated_info = next_account_info(account_info_iter)?;
+    let withdraw_info = next_account_info(account_info_iter)?;
+
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let _metadata_program = next_account_info(account_info_iter)?;
+    let _associated_program = next_account_info(account_info_iter)?;
+    let rate_limit_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    bridge_admin.pause.check_withdrawal(&lib::TokenType::FT)?;
+
+    verify_token_program(token_program)?;
+
+    if !fee_payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *metadata_info.key != Metadata::find_pda(mint_info.key).0 {
+        return Err(LibError::WrongMetadataAccount.into());
+    }
+
+    if signed_meta.as_ref().is_some_and(|meta| meta.collection.is_some()) {
+        return Err(LibError::WrongCollection.into());
+    }
+
+    if signed_meta.as_ref().is_some_and(|meta| meta.royalties.is_some()) {
+        return Err(LibError::WrongTokenType.into());
+    }
+
+    if let Some(token_seed) = token_seed {
+        try_mint_token_with_meta(
+            program_id,
+            bridge_admin_info,
+            token_seed,
             signed_meta,
             mint_info,
             metadata_info,
-            owner_info,
+            fee_payer_info,
             rent_info,
             system_program,
+            token_program,
             seeds,
         )?;
     }
 
     let metadata: mpl_token_metadata::accounts::Metadata = Metadata::from_bytes(&mut metadata_info.data.borrow_mut().as_ref())?;
 
-    let mint: spl_token::state::Mint = Mint::unpack_from_slice(&mut mint_info.data.borrow_mut().as_ref())?;
+    let mint = load_mint(token_program, mint_info)?;
 
     let content = Content::new(
         origin,
@@ -529,21 +1102,23 @@ pub fn process_withdraw_ft<'a>(
                 metadata.symbol.trim_matches(char::from(0)).to_string(),
                 metadata.uri.trim_matches(char::from(0)).to_string(),
                 mint.decimals,
-            ),
+            ).with_relayer_fee(relayer_fee).with_bitmap_index(bitmap_index, &bridge_admin_key),
         ),
-    );
+    ).with_call(call.clone());
 
-    verify_ecdsa_signature(get_merkle_root(content.hash(), &path)?.as_slice(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
+    verifier.verify(bridge_admin_info, &bridge_admin, instruction_sysvar_info, get_merkle_root(content.hash(), &path)?)?;
+
+    consume_rate_limit(program_id, bridge_admin_info, rate_limit_info, Some(mint_info.key), amount)?;
 
     if *bridge_associated_info.key !=
-        get_associated_token_address(&bridge_admin_key, mint_info.key) {
+        get_associated_token_address_with_program_id(&bridge_admin_key, mint_info.key, token_program.key) {
         return Err(LibError::WrongTokenAccount.into());
     }
 
     if bridge_associated_info.data.borrow().as_ref().len() == 0 {
         msg!("Create bridge associated account");
         lib::call_create_associated_account(
-            owner_info,
+            fee_payer_info,
             bridge_admin_info,
             mint_info,
             bridge_associated_info,
@@ -553,17 +1128,17 @@ pub fn process_withdraw_ft<'a>(
         )?;
     }
 
-    let bridge_associated = spl_token::state::Account::unpack_from_slice(&mut bridge_associated_info.data.borrow_mut().as_ref())?;
+    let bridge_balance = token_account_amount(bridge_associated_info)?;
 
     if *owner_associated_info.key !=
-        get_associated_token_address(&owner_info.key, mint_info.key) {
+        get_associated_token_address_with_program_id(owner_info.key, mint_info.key, token_program.key) {
         return Err(LibError::WrongTokenAccount.into());
     }
 
     if owner_associated_info.data.borrow().as_ref().len() == 0 {
         msg!("Create owner associated account");
         lib::call_create_associated_account(
-            owner_info,
+            fee_payer_info,
             owner_info,
             mint_info,
             owner_associated_info,
@@ -574,76 +1149,104 @@ pub fn process_withdraw_ft<'a>(
     }
 
 
-    if bridge_associated.amount < amount {
+    if bridge_balance < amount {
         msg!("Minting token to bridge admin");
         call_mint_to(
+            token_program,
             mint_info,
             bridge_associated_info,
             bridge_admin_info,
             seeds,
-            amount - bridge_associated.amount,
+            amount - bridge_balance,
         )?;
     }
 
+    let fee = relayer_fee.unwrap_or(0);
+
     msg!("Transferring token");
     call_transfer_token(
+        token_program,
         bridge_associated_info,
+        mint_info,
         owner_associated_info,
         bridge_admin_info,
-        amount,
+        amount - fee,
+        mint.decimals,
         &[&[seeds.as_slice()]],
     )?;
 
-    let (withdraw_key, bump_seed) = Pubkey::find_program_address(&[origin.as_slice()], program_id);
-    if withdraw_key != *withdraw_info.key {
-        return Err(LibError::WrongNonce.into());
+    if fee > 0 {
+        if *fee_payer_associated_info.key !=
+            get_associated_token_address_with_program_id(fee_payer_info.key, mint_info.key, token_program.key) {
+            return Err(LibError::WrongTokenAccount.into());
+        }
+
+        if fee_payer_associated_info.data.borrow().is_empty() {
+            msg!("Create fee payer associated account");
+            lib::call_create_associated_account(
+                fee_payer_info,
+                fee_payer_info,
+                mint_info,
+                fee_payer_associated_info,
+                rent_info,
+                system_program,
+                token_program,
+            )?;
+        }
+
+        msg!("Transferring relayer fee");
+        call_transfer_token(
+            token_program,
+            bridge_associated_info,
+            mint_info,
+            fee_payer_associated_info,
+            bridge_admin_info,
+            fee,
+            mint.decimals,
+            &[&[seeds.as_slice()]],
+        )?;
     }
 
-    msg!("Creating withdraw account");
-    lib::call_create_account(
-        owner_info,
+    record_withdraw(
+        program_id,
+        &bridge_admin,
+        &bridge_admin_key,
         withdraw_info,
+        fee_payer_info,
         rent_info,
         system_program,
-        WITHDRAW_SIZE,
-        program_id,
-        &[origin.as_slice(), &[bump_seed]],
+        origin,
+        bitmap_index,
+        lib::TokenType::FT,
+        Some(*mint_info.key),
+        amount,
+        *owner_info.key,
     )?;
 
-    msg!("Initializing withdraw account");
-    let mut withdraw: Withdraw = BorshDeserialize::deserialize(&mut withdraw_info.data.borrow_mut().as_ref())?;
-    if withdraw.is_initialized {
-        return Err(LibError::AlreadyInUse.into());
+    if let Some(call) = call {
+        call_target_program(program_id, bridge_admin_info, account_info_iter, call)?;
     }
 
-    withdraw.is_initialized = true;
-    withdraw.token_type = lib::TokenType::FT;
-    withdraw.origin = origin;
-    withdraw.mint = Option::Some(mint_info.key.clone());
-    withdraw.amount = amount;
-    withdraw.receiver_address = *owner_info.key;
-    withdraw.serialize(&mut *withdraw_info.data.borrow_mut())?;
-    msg!("Withdraw account created");
     Ok(())
 }
 
 pub fn process_withdraw_nft<'a>(
     program_id: &'a Pubkey,
-    accounts: &'a [AccountInfo<'a>],
+    account_info_iter: &mut Iter<'a, AccountInfo<'a>>,
+    verifier: &mut RootVerifier<'a>,
     seeds: [u8; 32],
-    signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    recovery_id: u8,
     path: Vec<[u8; 32]>,
     origin: [u8; 32],
     token_seed: Option<[u8; 32]>,
     signed_meta: Option<SignedMetadata>,
+    bitmap_index: Option<u64>,
+    call: Option<TargetCall>,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-
     let bridge_admin_info = next_account_info(account_info_iter)?;
     let mint_info = next_account_info(account_info_iter)?;
     let metadata_info = next_account_info(account_info_iter)?;
     let owner_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
     let owner_associated_info = next_account_info(account_info_iter)?;
     let bridge_associated_info = next_account_info(account_info_iter)?;
     let withdraw_info = next_account_info(account_info_iter)?;
@@ -651,8 +1254,27 @@ pub fn process_withdraw_nft<'a>(
     let token_program = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
-    let _metadata_program = next_account_info(account_info_iter)?;
-    let _associated_program = next_account_info(account_info_iter)?;
+    let metadata_program = next_account_info(account_info_iter)?;
+    let associated_program = next_account_info(account_info_iter)?;
+    let rate_limit_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    // Wrapped NFTs get the master edition on the first withdrawal
+    let edition_info = match token_seed {
+        Some(_) => Some(next_account_info(account_info_iter)?),
+        None => None,
+    };
+
+    // Wrapped NFTs with the signed collection are verified in it by the bridge admin
+    let collection_accounts = match (token_seed, signed_meta.as_ref().and_then(|meta| meta.collection)) {
+        (Some(_), Some(_)) => Some((next_account_info(account_info_iter)?, next_account_info(account_info_iter)?)),
+        _ => None,
+    };
+
+    let royalties = match token_seed {
+        Some(_) => signed_meta.as_ref().and_then(|meta| meta.royalties.clone()),
+        None => None,
+    };
 
     let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
     if *bridge_admin_info.key != bridge_admin_key {
@@ -664,10 +1286,24 @@ pub fn process_withdraw_nft<'a>(
         return Err(LibError::NotInitialized.into());
     }
 
+    bridge_admin.pause.check_withdrawal(&lib::TokenType::NFT)?;
+
+    verify_token_program(token_program)?;
+
+    if !fee_payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
     if *metadata_info.key != Metadata::find_pda(mint_info.key).0 {
         return Err(LibError::WrongMetadataAccount.into());
     }
 
+    if let Some(edition_info) = edition_info {
+        if *edition_info.key != MasterEdition::find_pda(mint_info.key).0 {
+            return Err(LibError::WrongMetadataAccount.into());
+        }
+    }
+
     if let Some(token_seed) = token_seed {
         try_mint_token_with_meta(
             program_id,
@@ -676,35 +1312,45 @@ pub fn process_withdraw_nft<'a>(
             signed_meta,
             mint_info,
             metadata_info,
-            owner_info,
+            fee_payer_info,
             rent_info,
             system_program,
+            token_program,
             seeds,
         )?;
     }
 
     let metadata: mpl_token_metadata::accounts::Metadata = Metadata::from_bytes(&mut metadata_info.data.borrow_mut().as_ref())?;
 
+    let mint = load_mint(token_program, mint_info)?;
+
+    let programmable = ProgrammableAccounts::next(account_info_iter, &metadata)?;
+
     // Default metadata - from token
     let mut name = metadata.name;
     let mut symbol = metadata.symbol;
     let mut uri = metadata.uri;
 
     let mut collection: Option<[u8; 32]> = None;
+    let mut collection_metadata_info = None;
+    let mut sized_collection = false;
+    let verified_collection = metadata.collection.as_ref().is_some_and(|collection| collection.verified);
 
     if metadata.collection.is_some() {
         let collection_key = metadata.collection.unwrap().key;
 
-        let collection_metadata_info = next_account_info(account_info_iter)?;
-        if *collection_metadata_info.key != Metadata::find_pda(&collection_key).0 {
+        let info = next_account_info(account_info_iter)?;
+        if *info.key != Metadata::find_pda(&collection_key).0 {
             return Err(LibError::WrongMetadataAccount.into());
         }
 
         // If collection exists, use its metadata (name and symbol) instead of token metadata
-        let collection_metadata: mpl_token_metadata::accounts::Metadata = Metadata::from_bytes(&mut collection_metadata_info.data.borrow_mut().as_ref())?;
+        let collection_metadata: mpl_token_metadata::accounts::Metadata = Metadata::from_bytes(&info.data.borrow())?;
         name = collection_metadata.name;
         symbol = collection_metadata.symbol;
-        collection = Some(collection_key.to_bytes())
+        sized_collection = collection_metadata.collection_details.is_some();
+        collection = Some(collection_key.to_bytes());
+        collection_metadata_info = Some(info);
     }
 
     let content = Content::new(
@@ -718,21 +1364,23 @@ pub fn process_withdraw_nft<'a>(
                 name.trim_matches(char::from(0)).to_string(),
                 symbol.trim_matches(char::from(0)).to_string(),
                 uri.trim_matches(char::from(0)).to_string(),
-            )
+            ).with_royalties(royalties).with_bitmap_index(bitmap_index, &bridge_admin_key),
         ),
-    );
+    ).with_call(call.clone());
+
+    verifier.verify(bridge_admin_info, &bridge_admin, instruction_sysvar_info, get_merkle_root(content.hash(), &path)?)?;
 
-    verify_ecdsa_signature(get_merkle_root(content.hash(), &path)?.as_slice(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
+    consume_rate_limit(program_id, bridge_admin_info, rate_limit_info, Some(mint_info.key), 1)?;
 
     if *bridge_associated_info.key !=
-        get_associated_token_address(&bridge_admin_key, mint_info.key) {
+        get_associated_token_address_with_program_id(&bridge_admin_key, mint_info.key, token_program.key) {
         return Err(LibError::WrongTokenAccount.into());
     }
 
     if bridge_associated_info.data.borrow().as_ref().len() == 0 {
         msg!("Create bridge associated account");
         lib::call_create_associated_account(
-            owner_info,
+            fee_payer_info,
             bridge_admin_info,
             mint_info,
             bridge_associated_info,
@@ -742,17 +1390,17 @@ pub fn process_withdraw_nft<'a>(
         )?;
     }
 
-    let bridge_associated = spl_token::state::Account::unpack_from_slice(&mut bridge_associated_info.data.borrow_mut().as_ref())?;
+    let bridge_balance = token_account_amount(bridge_associated_info)?;
 
     if *owner_associated_info.key !=
-        get_associated_token_address(&owner_info.key, mint_info.key) {
+        get_associated_token_address_with_program_id(owner_info.key, mint_info.key, token_program.key) {
         return Err(LibError::WrongTokenAccount.into());
     }
 
     if owner_associated_info.data.borrow().as_ref().len() == 0 {
         msg!("Deposit owner associated account");
         lib::call_create_associated_account(
-            owner_info,
+            fee_payer_info,
             owner_info,
             mint_info,
             owner_associated_info,
@@ -762,9 +1410,10 @@ pub fn process_withdraw_nft<'a>(
         )?;
     }
 
-    if bridge_associated.amount == 0 {
+    if bridge_balance == 0 {
         msg!("Minting token to bridge admin");
         call_mint_to(
+            token_program,
             mint_info,
             bridge_associated_info,
             bridge_admin_info,
@@ -773,172 +1422,1788 @@ pub fn process_withdraw_nft<'a>(
         )?;
     }
 
-    msg!("Transferring token");
-    call_transfer_token(
-        bridge_associated_info,
-        owner_associated_info,
-        bridge_admin_info,
-        1,
-        &[&[seeds.as_slice()]],
-    )?;
+    if let Some(edition_info) = edition_info {
+        if edition_info.data_is_empty() {
+            msg!("Creating master edition");
+            call_create_master_edition(
+                metadata_program,
+                token_program,
+                system_program,
+                rent_info,
+                mint_info,
+                metadata_info,
+                edition_info,
+                bridge_admin_info,
+                fee_payer_info,
+                seeds,
+            )?;
+        }
+    }
 
-    let (withdraw_key, bump_seed) = Pubkey::find_program_address(&[origin.as_slice()], program_id);
-    if withdraw_key != *withdraw_info.key {
-        return Err(LibError::WrongNonce.into());
+    // Tokens wrapped before the collection was signed stay without it
+    if let (Some((collection_mint_info, collection_edition_info)), Some(collection_metadata_info)) = (collection_accounts, collection_metadata_info) {
+        if collection != Some(collection_mint_info.key.to_bytes()) {
+            return Err(LibError::WrongCollection.into());
+        }
+
+        if !verified_collection {
+            msg!("Verifying collection");
+            call_verify_collection(
+                metadata_program,
+                metadata_info,
+                bridge_admin_info,
+                fee_payer_info,
+                collection_mint_info,
+                collection_metadata_info,
+                collection_edition_info,
+                sized_collection,
+                seeds,
+            )?;
+        }
     }
 
-    msg!("Creating withdraw account");
-    lib::call_create_account(
-        owner_info,
+    if let Some(programmable) = programmable {
+        msg!("Transferring programmable token");
+        programmable.call_transfer(
+            token_program,
+            associated_program,
+            system_program,
+            mint_info,
+            metadata_info,
+            bridge_associated_info,
+            bridge_admin_info,
+            programmable.bridge_token_record,
+            owner_associated_info,
+            owner_info,
+            programmable.owner_token_record,
+            fee_payer_info,
+            &[&[seeds.as_slice()]],
+        )?;
+    } else {
+        msg!("Transferring token");
+        call_transfer_token(
+            token_program,
+            bridge_associated_info,
+            mint_info,
+            owner_associated_info,
+            bridge_admin_info,
+            1,
+            mint.decimals,
+            &[&[seeds.as_slice()]],
+        )?;
+    }
+
+    record_withdraw(
+        program_id,
+        &bridge_admin,
+        &bridge_admin_key,
         withdraw_info,
+        fee_payer_info,
         rent_info,
         system_program,
-        WITHDRAW_SIZE,
-        program_id,
-        &[origin.as_slice(), &[bump_seed]],
+        origin,
+        bitmap_index,
+        lib::TokenType::NFT,
+        Some(*mint_info.key),
+        1,
+        *owner_info.key,
     )?;
 
-    msg!("Initializing withdraw account");
-    let mut withdraw: Withdraw = BorshDeserialize::deserialize(&mut withdraw_info.data.borrow_mut().as_ref())?;
-    if withdraw.is_initialized {
-        return Err(LibError::AlreadyInUse.into());
+    if let Some(call) = call {
+        call_target_program(program_id, bridge_admin_info, account_info_iter, call)?;
     }
 
-    withdraw.is_initialized = true;
-    withdraw.token_type = lib::TokenType::NFT;
-    withdraw.origin = origin;
-    withdraw.mint = Option::Some(mint_info.key.clone());
-    withdraw.amount = 1;
-    withdraw.receiver_address = *owner_info.key;
-    withdraw.serialize(&mut *withdraw_info.data.borrow_mut())?;
-    msg!("Withdraw account created");
     Ok(())
 }
 
-pub fn verify_commission_charged<'a>(
-    bridge_admin_info: &AccountInfo<'a>,
-    instruction_sysvar_info: &AccountInfo<'a>,
-    admin: &BridgeAdmin,
-    token: lib::TokenType,
-    amount: u64,
+pub fn process_withdraw_cnft<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    proof: RootProof,
+    path: Vec<[u8; 32]>,
+    origin: [u8; 32],
+    leaf: Option<CompressedLeaf>,
+    token_seed: Option<[u8; 32]>,
+    signed_meta: Option<SignedMetadata>,
+    bitmap_index: Option<u64>,
 ) -> ProgramResult {
-    let current_index = load_current_index_checked(instruction_sysvar_info)?;
-    let commission_instruction = load_instruction_at_checked((current_index - 1) as usize, instruction_sysvar_info)?;
+    let account_info_iter = &mut accounts.iter();
 
-    if commission_instruction.program_id != admin.commission_program {
-        return Err(LibError::WrongCommissionProgram.into());
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let withdraw_info = next_account_info(account_info_iter)?;
+    let tree_config_info = next_account_info(account_info_iter)?;
+    let merkle_tree_info = next_account_info(account_info_iter)?;
+
+    let bubblegum_program = next_account_info(account_info_iter)?;
+    let log_wrapper = next_account_info(account_info_iter)?;
+    let compression_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let rate_limit_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+    let wrapped_asset_info = match leaf {
+        Some(_) => None,
+        None => Some(next_account_info(account_info_iter)?),
+    };
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
     }
 
-    let commission_key = Pubkey::create_program_address(&[lib::COMMISSION_ADMIN_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()], &commission_instruction.program_id)?;
-    if commission_key != commission_instruction.accounts[0].pubkey {
-        return Err(LibError::WrongCommissionAccount.into());
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
     }
 
-    let instruction = lib::instructions::commission::CommissionInstruction::try_from_slice(commission_instruction.data.as_slice())?;
+    bridge_admin.pause.check_withdrawal(&lib::TokenType::CNFT)?;
 
-    if let lib::instructions::commission::CommissionInstruction::ChargeCommission(args) = instruction {
-        if args.deposit_token == token && args.deposit_token_amount == amount {
-            return Ok(());
+    if !fee_payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Proof nodes are the remaining accounts except the SignedRoot account
+    let leaf_proof: Vec<AccountInfo<'a>> = account_info_iter
+        .filter(|info| !matches!(&proof, RootProof::SignedRoot(key) if key == info.key))
+        .cloned()
+        .collect();
+
+    let asset_id = lib::instructions::bridge::get_compressed_asset_id(program_id, merkle_tree_info.key, &leaf, token_seed);
+
+    let transfer = match (&leaf, &signed_meta) {
+        (Some(leaf), _) => TransferData::new_nft_transfer(
+            asset_id.to_bytes(),
+            leaf.metadata.collection.as_ref().map(|c| c.key.to_bytes()),
+            leaf.metadata.name.clone(),
+            leaf.metadata.symbol.clone(),
+            leaf.metadata.uri.clone(),
+        ),
+        (None, Some(meta)) => TransferData::new_nft_transfer(
+            asset_id.to_bytes(),
+            None,
+            meta.name.clone(),
+            meta.symbol.clone(),
+            meta.uri.clone(),
+        ).with_royalties(meta.royalties.clone()),
+        (None, None) => return Err(LibError::NoTokenMeta.into()),
+    };
+
+    let content = Content::new(
+        origin,
+        owner_info.key.to_bytes(),
+        program_id.to_bytes(),
+        Box::new(transfer.with_compressed().with_bitmap_index(bitmap_index, &bridge_admin_key)),
+    );
+
+    let verifier = &mut RootVerifier::new(program_id, accounts, proof);
+    verifier.verify(bridge_admin_info, &bridge_admin, instruction_sysvar_info, get_merkle_root(content.hash(), &path)?)?;
+
+    consume_rate_limit(program_id, bridge_admin_info, rate_limit_info, Some(&asset_id), 1)?;
+
+    if let Some(leaf) = leaf {
+        msg!("Transferring leaf");
+        call_transfer_leaf(
+            bubblegum_program,
+            tree_config_info,
+            bridge_admin_info,
+            bridge_admin_info,
+            owner_info,
+            merkle_tree_info,
+            log_wrapper,
+            compression_program,
+            system_program,
+            &leaf,
+            &leaf_proof,
+            &[&[seeds.as_slice()]],
+        )?;
+    } else if let (Some(signed_meta), Some(token_seed), Some(wrapped_asset_info)) = (signed_meta, token_seed, wrapped_asset_info) {
+        verify_bridge_tree(&bridge_admin_key, tree_config_info, merkle_tree_info)?;
+
+        let bump_seed = verify_wrapped_asset(program_id, &bridge_admin_key, wrapped_asset_info, token_seed)?;
+        if wrapped_asset_info.data_is_empty() {
+            msg!("Creating wrapped asset account");
+            lib::call_create_account(
+                fee_payer_info,
+                wrapped_asset_info,
+                rent_info,
+                system_program,
+                WRAPPED_ASSET_SIZE,
+                program_id,
+                &[lib::WRAPPED_ASSET_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), token_seed.as_slice(), &[bump_seed]],
+            )?;
+        }
+
+        // The previous leaf should be deposited (burned) before the next one is minted
+        let wrapped_asset: WrappedAsset = BorshDeserialize::deserialize(&mut wrapped_asset_info.data.borrow().as_ref())?;
+        if wrapped_asset.asset_id.is_some() {
+            return Err(LibError::AlreadyMinted.into());
+        }
+
+        // Bubblegum uses the number of minted leaves as the nonce of the new one
+        let tree_config = TreeConfig::from_bytes(&tree_config_info.data.borrow())?;
+        let leaf_asset_id = mpl_bubblegum::utils::get_asset_id(merkle_tree_info.key, tree_config.num_minted);
+
+        let (seller_fee_basis_points, creators) = match signed_meta.royalties {
+            Some(royalties) => (royalties.seller_fee_basis_points, royalties.creators),
+            None => (0, Vec::new()),
+        };
+
+        let metadata = MetadataArgs {
+            name: signed_meta.name,
+            symbol: signed_meta.symbol,
+            uri: signed_meta.uri,
+            seller_fee_basis_points,
+            primary_sale_happened: false,
+            // Updated by the bridge admin as the tree delegate, same as wrapped NFT metadata
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(LeafTokenStandard::NonFungible),
+            collection: None,
+            uses: None,
+            token_program_version: TokenProgramVersion::Original,
+            creators: creators.into_iter().map(|creator| LeafCreator {
+                address: creator.address,
+                verified: false,
+                share: creator.share,
+            }).collect(),
+        };
+
+        msg!("Minting wrapped leaf");
+        call_mint_leaf(
+            bubblegum_program,
+            tree_config_info,
+            owner_info,
+            merkle_tree_info,
+            fee_payer_info,
+            bridge_admin_info,
+            log_wrapper,
+            compression_program,
+            system_program,
+            metadata,
+            seeds,
+        )?;
+
+        WrappedAsset {
+            asset_id: Some(leaf_asset_id),
+            is_initialized: true,
+        }.serialize(&mut *wrapped_asset_info.data.borrow_mut())?;
+    }
+
+    record_withdraw(
+        program_id,
+        &bridge_admin,
+        &bridge_admin_key,
+        withdraw_info,
+        fee_payer_info,
+        rent_info,
+        system_program,
+        origin,
+        bitmap_index,
+        lib::TokenType::CNFT,
+        Some(asset_id),
+        1,
+        *owner_info.key,
+    )
+}
+
+pub fn process_withdraw_batch<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    proof: RootProof,
+    transfers: Vec<WithdrawTransfer>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let verifier = &mut RootVerifier::new(program_id, accounts, proof);
+
+    for t in transfers {
+        match t.token_type {
+            lib::TokenType::Native => process_withdraw_native(program_id, account_info_iter, verifier, seeds, t.path, t.origin, t.amount, t.relayer_fee, t.bitmap_index, t.wrapped_sol, None)?,
+            lib::TokenType::FT => process_withdraw_ft(program_id, account_info_iter, verifier, seeds, t.path, t.origin, t.amount, t.token_seed, t.signed_meta, t.relayer_fee, t.bitmap_index, None)?,
+            lib::TokenType::NFT => process_withdraw_nft(program_id, account_info_iter, verifier, seeds, t.path, t.origin, t.token_seed, t.signed_meta, t.bitmap_index, None)?,
+            lib::TokenType::Message | lib::TokenType::CNFT => return Err(LibError::WrongTokenType.into()),
+        }
+    }
+
+    Ok(())
+}
+
+// Verifies proof for the first Merkle root, the next roots should be the same
+pub struct RootVerifier<'a> {
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    proof: RootProof,
+    root: Option<[u8; 32]>,
+}
+
+impl<'a> RootVerifier<'a> {
+    pub fn new(program_id: &'a Pubkey, accounts: &'a [AccountInfo<'a>], proof: RootProof) -> Self {
+        RootVerifier {
+            program_id,
+            accounts,
+            proof,
+            root: None,
+        }
+    }
+
+    pub fn verify(&mut self, bridge_admin_info: &AccountInfo, bridge_admin: &BridgeAdmin, instruction_sysvar_info: &AccountInfo, root: [u8; 32]) -> ProgramResult {
+        if let Some(verified) = self.root {
+            if verified != root {
+                return Err(LibError::WrongMerkleRoot.into());
+            }
+
+            return Ok(());
+        }
+
+        match &self.proof {
+            RootProof::Signatures(signatures) => bridge_admin.signers.verify(root.as_slice(), signatures, instruction_sysvar_info)?,
+            RootProof::SignedRoot(key) => verify_signed_root(self.program_id, self.accounts, key, bridge_admin_info, bridge_admin, root)?,
+        }
+
+        self.root = Some(root);
+        Ok(())
+    }
+}
+
+// Checks that the SignedRoot account stores the root submitted under the current signers
+fn verify_signed_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    signed_root_key: &Pubkey,
+    bridge_admin_info: &AccountInfo,
+    bridge_admin: &BridgeAdmin,
+    root: [u8; 32],
+) -> ProgramResult {
+    let signed_root_info = match accounts.iter().find(|a| *a.key == *signed_root_key) {
+        Some(info) => info,
+        None => return Err(LibError::WrongSignedRoot.into()),
+    };
+
+    if *signed_root_info.owner != *program_id || signed_root_info.data_is_empty() {
+        return Err(LibError::WrongSignedRoot.into());
+    }
+
+    let signed_root: SignedRoot = BorshDeserialize::deserialize(&mut signed_root_info.data.borrow().as_ref())?;
+    if !signed_root.is_initialized || signed_root.bridge_admin != *bridge_admin_info.key || signed_root.root != root {
+        return Err(LibError::WrongSignedRoot.into());
+    }
+
+    let signed_root_address = Pubkey::create_program_address(
+        &[lib::SIGNED_ROOT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), root.as_slice(), &[signed_root.bump]],
+        program_id,
+    )?;
+    if signed_root_address != *signed_root_key {
+        return Err(LibError::WrongSignedRoot.into());
+    }
+
+    // Roots submitted by the previous signer set are not accepted after ownership transfer
+    if signed_root.signers_hash != bridge_admin.signers.hash()? {
+        return Err(LibError::WrongSignedRoot.into());
+    }
+
+    Ok(())
+}
+
+// Protects from the withdrawal replay: creates the Withdraw account for the origin in the receipt mode
+// or sets the bit of the signed withdrawal index in the bitmap mode
+fn record_withdraw<'a>(
+    program_id: &Pubkey,
+    bridge_admin: &BridgeAdmin,
+    bridge_admin_key: &Pubkey,
+    withdraw_info: &AccountInfo<'a>,
+    fee_payer_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    origin: [u8; 32],
+    bitmap_index: Option<u64>,
+    token_type: lib::TokenType,
+    mint: Option<Pubkey>,
+    amount: u64,
+    receiver: Pubkey,
+) -> ProgramResult {
+    match (bridge_admin.withdraw_mode, bitmap_index) {
+        (WithdrawMode::Receipt, None) => {}
+        (WithdrawMode::Bitmap, Some(bitmap_index)) => {
+            return set_withdraw_bit(program_id, bridge_admin_key, withdraw_info, fee_payer_info, rent_info, system_program, bitmap_index);
+        }
+        _ => return Err(LibError::WrongWithdrawMode.into()),
+    }
+
+    let (withdraw_key, bump_seed) = Pubkey::find_program_address(&[origin.as_slice()], program_id);
+    if withdraw_key != *withdraw_info.key {
+        return Err(LibError::WrongNonce.into());
+    }
+
+    msg!("Creating withdraw account");
+    lib::call_create_account(
+        fee_payer_info,
+        withdraw_info,
+        rent_info,
+        system_program,
+        WITHDRAW_SIZE,
+        program_id,
+        &[origin.as_slice(), &[bump_seed]],
+    )?;
+
+    msg!("Initializing withdraw account");
+    let mut withdraw: Withdraw = BorshDeserialize::deserialize(&mut withdraw_info.data.borrow_mut().as_ref())?;
+    if withdraw.is_initialized {
+        return Err(LibError::AlreadyInUse.into());
+    }
+
+    withdraw.is_initialized = true;
+    withdraw.token_type = token_type;
+    withdraw.origin = origin;
+    withdraw.mint = mint;
+    withdraw.amount = amount;
+    withdraw.receiver_address = receiver;
+    withdraw.payer = *fee_payer_info.key;
+    withdraw.serialize(&mut *withdraw_info.data.borrow_mut())?;
+    msg!("Withdraw account created");
+    Ok(())
+}
+
+// Sets the bit of the withdrawal index, creates the WithdrawBitmap shard if it does not exist
+fn set_withdraw_bit<'a>(
+    program_id: &Pubkey,
+    bridge_admin_key: &Pubkey,
+    bitmap_info: &AccountInfo<'a>,
+    fee_payer_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    bitmap_index: u64,
+) -> ProgramResult {
+    let shard = bitmap_index / WITHDRAW_BITMAP_BITS;
+
+    let mut bitmap: WithdrawBitmap = if bitmap_info.data_is_empty() {
+        let (bitmap_key, bump_seed) = Pubkey::find_program_address(
+            &[lib::WITHDRAW_BITMAP_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), shard.to_be_bytes().as_slice()],
+            program_id,
+        );
+        if bitmap_key != *bitmap_info.key {
+            return Err(LibError::WrongNonce.into());
+        }
+
+        msg!("Creating withdraw bitmap account");
+        lib::call_create_account(
+            fee_payer_info,
+            bitmap_info,
+            rent_info,
+            system_program,
+            WITHDRAW_BITMAP_SIZE,
+            program_id,
+            &[lib::WITHDRAW_BITMAP_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), shard.to_be_bytes().as_slice(), &[bump_seed]],
+        )?;
+
+        WithdrawBitmap {
+            shard,
+            bits: vec![0; (WITHDRAW_BITMAP_BITS / 8) as usize],
+            bump: bump_seed,
+            is_initialized: true,
+        }
+    } else {
+        if *bitmap_info.owner != *program_id {
+            return Err(LibError::WrongNonce.into());
+        }
+
+        let bitmap: WithdrawBitmap = BorshDeserialize::deserialize(&mut bitmap_info.data.borrow().as_ref())?;
+        let bitmap_key = Pubkey::create_program_address(
+            &[lib::WITHDRAW_BITMAP_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), shard.to_be_bytes().as_slice(), &[bitmap.bump]],
+            program_id,
+        )?;
+        if !bitmap.is_initialized || bitmap.shard != shard || bitmap_key != *bitmap_info.key {
+            return Err(LibError::WrongNonce.into());
+        }
+
+        bitmap
+    };
+
+    msg!("Setting withdraw bit {}", bitmap_index);
+    bitmap.set(bitmap_index)?;
+    bitmap.serialize(&mut *bitmap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+// Calls target program signing by the call authority PDA. All remaining accounts are passed to the call.
+
+fn call_target_program<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    account_info_iter: &mut Iter<'a, AccountInfo<'a>>,
+    call: TargetCall,
+) -> ProgramResult {
+    let target_program_info = next_account_info(account_info_iter)?;
+    let call_authority_info = next_account_info(account_info_iter)?;
+
+    if *target_program_info.key != call.program || call.program == *program_id || !target_program_info.executable {
+        return Err(LibError::WrongTargetProgram.into());
+    }
+
+    let (call_authority_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::CALL_AUTHORITY_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), call.program.as_ref()],
+        program_id,
+    );
+    if call_authority_key != *call_authority_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    // Only the signed accounts are passed and none of them as signer,
+    // so the target can not spend the fee payer lamports or act for other signers of the withdrawal
+    let mut metas = vec![AccountMeta::new_readonly(call_authority_key, true)];
+    let mut infos = vec![call_authority_info.clone()];
+    for account in &call.accounts {
+        let info = next_account_info(account_info_iter)?;
+        if *info.key != account.pubkey || (account.is_writable && !info.is_writable) {
+            return Err(LibError::WrongCallAccounts.into());
+        }
+
+        if account.is_writable {
+            metas.push(AccountMeta::new(account.pubkey, false));
+        } else {
+            metas.push(AccountMeta::new_readonly(account.pubkey, false));
+        }
+        infos.push(info.clone());
+    }
+    infos.push(target_program_info.clone());
+
+    msg!("Calling target program");
+    invoke_signed(
+        &Instruction {
+            program_id: call.program,
+            accounts: metas,
+            data: call.payload,
+        },
+        infos.as_slice(),
+        &[&[lib::CALL_AUTHORITY_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), call.program.as_ref(), &[bump_seed]]],
+    )
+}
+
+fn next_deposit_id<'a>(
+    bridge_admin_info: &AccountInfo<'a>,
+    bridge_admin: &mut BridgeAdmin,
+) -> Result<u64, ProgramError> {
+    let deposit_id = bridge_admin.deposit_nonce;
+    bridge_admin.deposit_nonce += 1;
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+    Ok(deposit_id)
+}
+
+fn create_deposit_record<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    deposit_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    event: &DepositEvent,
+) -> ProgramResult {
+    let nonce = event.deposit_id.to_be_bytes();
+    let (deposit_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::DEPOSIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), nonce.as_slice()],
+        program_id,
+    );
+    if deposit_key != *deposit_info.key {
+        return Err(LibError::WrongNonce.into());
+    }
+
+    msg!("Creating deposit account");
+    lib::call_create_account(
+        payer_info,
+        deposit_info,
+        rent_info,
+        system_program,
+        DEPOSIT_SIZE,
+        program_id,
+        &[lib::DEPOSIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), nonce.as_slice(), &[bump_seed]],
+    )?;
+
+    let mut deposit: Deposit = BorshDeserialize::deserialize(&mut deposit_info.data.borrow_mut().as_ref())?;
+    if deposit.is_initialized {
+        return Err(LibError::AlreadyInUse.into());
+    }
+
+    deposit.is_initialized = true;
+    deposit.nonce = event.deposit_id;
+    deposit.token_type = event.token_type.clone();
+    deposit.mint = event.mint;
+    deposit.amount = event.amount;
+    deposit.sender = event.sender;
+    deposit.network_to = event.network_to.clone();
+    deposit.receiver_address = event.receiver_address.clone();
+    deposit.bundle_salt = event.bundle_salt;
+    deposit.slot = Clock::get()?.slot;
+    deposit.serialize(&mut *deposit_info.data.borrow_mut())?;
+    msg!("Deposit account created");
+    Ok(())
+}
+
+pub fn process_send_message<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    network: String,
+    target: String,
+    payload: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let sysvar_info = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    bridge_admin.pause.check_deposit(&lib::TokenType::Message)?;
+
+    if !sender_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    verify_network(program_id, bridge_admin_info, network_registry_info, &network, &target)?;
+
+    verify_commission_charged(bridge_admin_info, sysvar_info, &bridge_admin, lib::TokenType::Message, 0)?;
+
+    let message_id = bridge_admin.message_nonce;
+    bridge_admin.message_nonce += 1;
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+    msg!("Message id: {}", message_id);
+
+    let event = MessageEvent {
+        message_id,
+        sender: *sender_info.key,
+        network_to: network,
+        target_address: target,
+        payload,
+    };
+
+    if let Ok(message_info) = next_account_info(account_info_iter) {
+        let nonce = message_id.to_be_bytes();
+        let (message_key, bump_seed) = Pubkey::find_program_address(
+            &[lib::MESSAGE_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), nonce.as_slice()],
+            program_id,
+        );
+        if message_key != *message_info.key {
+            return Err(LibError::WrongNonce.into());
+        }
+
+        msg!("Creating message account");
+        lib::call_create_account(
+            sender_info,
+            message_info,
+            rent_info,
+            system_program,
+            MESSAGE_SIZE,
+            program_id,
+            &[lib::MESSAGE_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), nonce.as_slice(), &[bump_seed]],
+        )?;
+
+        let mut message: Message = BorshDeserialize::deserialize(&mut message_info.data.borrow_mut().as_ref())?;
+        if message.is_initialized {
+            return Err(LibError::AlreadyInUse.into());
+        }
+
+        message.is_initialized = true;
+        message.nonce = message_id;
+        message.sender = event.sender;
+        message.network_to = event.network_to.clone();
+        message.target_address = event.target_address.clone();
+        message.payload = event.payload.clone();
+        message.slot = Clock::get()?.slot;
+        message.serialize(&mut *message_info.data.borrow_mut())?;
+        msg!("Message account created");
+    }
+
+    event.emit()
+}
+
+pub fn process_receive_message<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    origin: [u8; 32],
+    sender: String,
+    payload: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let receiver_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let receipt_info = next_account_info(account_info_iter)?;
+
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    bridge_admin.pause.check_withdrawal(&lib::TokenType::Message)?;
+
+    let content = Content::new(
+        origin,
+        receiver_info.key.to_bytes(),
+        program_id.to_bytes(),
+        Box::new(
+            MessageData::new(
+                sender.clone(),
+                payload.clone(),
+            ),
+        ),
+    );
+
+    bridge_admin.signers.verify(get_merkle_root(content.hash(), &path)?.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    // Receipts share the origin namespace with withdrawals, so every origin can be processed only once
+    let (receipt_key, bump_seed) = Pubkey::find_program_address(&[origin.as_slice()], program_id);
+    if receipt_key != *receipt_info.key {
+        return Err(LibError::WrongNonce.into());
+    }
+
+    msg!("Creating message receipt account");
+    lib::call_create_account(
+        payer_info,
+        receipt_info,
+        rent_info,
+        system_program,
+        MESSAGE_RECEIPT_SIZE,
+        program_id,
+        &[origin.as_slice(), &[bump_seed]],
+    )?;
+
+    let mut receipt: MessageReceipt = BorshDeserialize::deserialize(&mut receipt_info.data.borrow_mut().as_ref())?;
+    if receipt.is_initialized {
+        return Err(LibError::AlreadyInUse.into());
+    }
+
+    receipt.is_initialized = true;
+    receipt.origin = origin;
+    receipt.sender = sender;
+    receipt.receiver_address = *receiver_info.key;
+    receipt.payload = payload;
+    receipt.serialize(&mut *receipt_info.data.borrow_mut())?;
+    msg!("Message receipt account created");
+    Ok(())
+}
+
+pub fn process_set_pause<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    pause: PauseState,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let content = OperationContent::new(
+        bridge_admin.admin_nonce,
+        bridge_admin_key,
+        OperationType::SetPause,
+        vec![pause.deposits, pause.withdrawals],
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    msg!("Paused deposits: {:#010b}, withdrawals: {:#010b}", pause.deposits, pause.withdrawals);
+
+    bridge_admin.pause = pause;
+    bridge_admin.admin_nonce += 1;
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_set_rate_limit<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    mint: Option<Pubkey>,
+    capacity: u64,
+    refill_per_second: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let rate_limit_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let mint_key = mint.unwrap_or_default();
+    let (rate_limit_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::RATE_LIMIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), mint_key.as_ref()],
+        program_id,
+    );
+    if rate_limit_key != *rate_limit_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    // The signature is verified before the account is created, the first operation has zero nonce
+    let nonce = if rate_limit_info.data_is_empty() {
+        0
+    } else {
+        let rate_limit: RateLimit = BorshDeserialize::deserialize(&mut rate_limit_info.data.borrow().as_ref())?;
+        rate_limit.nonce
+    };
+
+    let mut data = Vec::from(mint_key.to_bytes());
+    data.append(&mut amount_bytes(capacity));
+    data.append(&mut amount_bytes(refill_per_second));
+
+    let content = OperationContent::new(
+        nonce,
+        bridge_admin_key,
+        OperationType::SetRateLimit,
+        data,
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    if rate_limit_info.data_is_empty() {
+        msg!("Creating rate limit account");
+        lib::call_create_account(
+            fee_payer_info,
+            rate_limit_info,
+            rent_info,
+            system_program,
+            RATE_LIMIT_SIZE,
+            program_id,
+            &[lib::RATE_LIMIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), mint_key.as_ref(), &[bump_seed]],
+        )?;
+    }
+
+    let mut rate_limit: RateLimit = BorshDeserialize::deserialize(&mut rate_limit_info.data.borrow_mut().as_ref())?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if rate_limit.is_initialized {
+        rate_limit.update(capacity, refill_per_second, now);
+    } else {
+        rate_limit.is_initialized = true;
+        rate_limit.mint = mint;
+        rate_limit.capacity = capacity;
+        rate_limit.refill_per_second = refill_per_second;
+        rate_limit.available = capacity;
+        rate_limit.last_update = now;
+    }
+
+    msg!("Rate limit capacity: {}, refill per second: {}", capacity, refill_per_second);
+
+    rate_limit.nonce += 1;
+    rate_limit.serialize(&mut *rate_limit_info.data.borrow_mut())?;
+    Ok(())
+}
+
+// Withdrawals are not limited if the rate limit for the token has not been set
+fn consume_rate_limit<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    rate_limit_info: &AccountInfo<'a>,
+    mint: Option<&Pubkey>,
+    amount: u64,
+) -> ProgramResult {
+    let (rate_limit_key, _) = Pubkey::find_program_address(
+        &[lib::RATE_LIMIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), mint.cloned().unwrap_or_default().as_ref()],
+        program_id,
+    );
+    if rate_limit_key != *rate_limit_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    if rate_limit_info.data_is_empty() {
+        return Ok(());
+    }
+
+    let mut rate_limit: RateLimit = BorshDeserialize::deserialize(&mut rate_limit_info.data.borrow_mut().as_ref())?;
+    if !rate_limit.is_initialized {
+        return Ok(());
+    }
+
+    rate_limit.consume(amount, Clock::get()?.unix_timestamp)?;
+    rate_limit.serialize(&mut *rate_limit_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_set_deposit_limit<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    mint: Option<Pubkey>,
+    min_amount: u64,
+    max_amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let deposit_limit_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let mint_key = mint.unwrap_or_default();
+    let (deposit_limit_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::DEPOSIT_LIMIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), mint_key.as_ref()],
+        program_id,
+    );
+    if deposit_limit_key != *deposit_limit_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    // The signature is verified before the account is created, the first operation has zero nonce
+    let nonce = if deposit_limit_info.data_is_empty() {
+        0
+    } else {
+        let deposit_limit: DepositLimit = BorshDeserialize::deserialize(&mut deposit_limit_info.data.borrow().as_ref())?;
+        deposit_limit.nonce
+    };
+
+    let mut data = Vec::from(mint_key.to_bytes());
+    data.append(&mut amount_bytes(min_amount));
+    data.append(&mut amount_bytes(max_amount));
+
+    let content = OperationContent::new(
+        nonce,
+        bridge_admin_key,
+        OperationType::SetDepositLimit,
+        data,
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    if deposit_limit_info.data_is_empty() {
+        msg!("Creating deposit limit account");
+        lib::call_create_account(
+            fee_payer_info,
+            deposit_limit_info,
+            rent_info,
+            system_program,
+            DEPOSIT_LIMIT_SIZE,
+            program_id,
+            &[lib::DEPOSIT_LIMIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), mint_key.as_ref(), &[bump_seed]],
+        )?;
+    }
+
+    let mut deposit_limit: DepositLimit = BorshDeserialize::deserialize(&mut deposit_limit_info.data.borrow_mut().as_ref())?;
+
+    msg!("Deposit min amount: {}, max amount: {}", min_amount, max_amount);
+
+    deposit_limit.is_initialized = true;
+    deposit_limit.mint = mint;
+    deposit_limit.min_amount = min_amount;
+    deposit_limit.max_amount = max_amount;
+    deposit_limit.nonce += 1;
+    deposit_limit.serialize(&mut *deposit_limit_info.data.borrow_mut())?;
+    Ok(())
+}
+
+// Deposits are not limited if the limit for the token has not been set
+fn verify_deposit_limit<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    deposit_limit_info: &AccountInfo<'a>,
+    mint: Option<&Pubkey>,
+    amount: u64,
+) -> ProgramResult {
+    let (deposit_limit_key, _) = Pubkey::find_program_address(
+        &[lib::DEPOSIT_LIMIT_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref(), mint.cloned().unwrap_or_default().as_ref()],
+        program_id,
+    );
+    if deposit_limit_key != *deposit_limit_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    if deposit_limit_info.data_is_empty() {
+        return Ok(());
+    }
+
+    let deposit_limit: DepositLimit = BorshDeserialize::deserialize(&mut deposit_limit_info.data.borrow_mut().as_ref())?;
+    if !deposit_limit.is_initialized {
+        return Ok(());
+    }
+
+    deposit_limit.check(amount)
+}
+
+pub fn process_set_token_policy<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    mode: TokenPolicyMode,
+    mints: Vec<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let token_policy_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let (token_policy_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::TOKEN_POLICY_PDA_SEED.as_bytes(), bridge_admin_key.as_ref()],
+        program_id,
+    );
+    if token_policy_key != *token_policy_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    // The signature is verified before the account is created, the first operation has zero nonce
+    let nonce = if token_policy_info.data_is_empty() {
+        0
+    } else {
+        let token_policy: TokenPolicy = BorshDeserialize::deserialize(&mut token_policy_info.data.borrow().as_ref())?;
+        token_policy.nonce
+    };
+
+    let mut data = vec![(&mode).into()];
+    for mint in &mints {
+        data.extend_from_slice(mint.as_ref());
+    }
+
+    let content = OperationContent::new(
+        nonce,
+        bridge_admin_key,
+        OperationType::SetTokenPolicy,
+        data,
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    if token_policy_info.data_is_empty() {
+        msg!("Creating token policy account");
+        lib::call_create_account(
+            fee_payer_info,
+            token_policy_info,
+            rent_info,
+            system_program,
+            TOKEN_POLICY_SIZE,
+            program_id,
+            &[lib::TOKEN_POLICY_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), &[bump_seed]],
+        )?;
+    }
+
+    let mut token_policy: TokenPolicy = BorshDeserialize::deserialize(&mut token_policy_info.data.borrow_mut().as_ref())?;
+
+    msg!("Token policy mode: {:?}, tokens: {}", mode, mints.len());
+
+    token_policy.is_initialized = true;
+    token_policy.mode = mode;
+    token_policy.mints = mints;
+    token_policy.nonce += 1;
+    token_policy.serialize(&mut *token_policy_info.data.borrow_mut())?;
+    Ok(())
+}
+
+// All tokens are accepted if the token policy has not been set
+fn verify_token_policy<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    token_policy_info: &AccountInfo<'a>,
+    mint: &Pubkey,
+) -> ProgramResult {
+    let (token_policy_key, _) = Pubkey::find_program_address(
+        &[lib::TOKEN_POLICY_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()],
+        program_id,
+    );
+    if token_policy_key != *token_policy_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    if token_policy_info.data_is_empty() {
+        return Ok(());
+    }
+
+    let token_policy: TokenPolicy = BorshDeserialize::deserialize(&mut token_policy_info.data.borrow_mut().as_ref())?;
+    if !token_policy.is_initialized {
+        return Ok(());
+    }
+
+    token_policy.check(mint)
+}
+
+pub fn process_init_network_registry<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let (network_registry_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::NETWORK_REGISTRY_PDA_SEED.as_bytes(), bridge_admin_key.as_ref()],
+        program_id,
+    );
+    if network_registry_key != *network_registry_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let content = OperationContent::new(
+        bridge_admin.admin_nonce,
+        bridge_admin_key,
+        OperationType::InitNetworkRegistry,
+        vec![],
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    lib::call_create_account(
+        fee_payer_info,
+        network_registry_info,
+        rent_info,
+        system_program,
+        NETWORK_REGISTRY_SIZE,
+        program_id,
+        &[lib::NETWORK_REGISTRY_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), &[bump_seed]],
+    )?;
+
+    let mut network_registry: NetworkRegistry = BorshDeserialize::deserialize(&mut network_registry_info.data.borrow_mut().as_ref())?;
+    if network_registry.is_initialized {
+        return Err(LibError::AlreadyInUse.into());
+    }
+
+    network_registry.networks = Vec::new();
+    network_registry.nonce = 0;
+    network_registry.is_initialized = true;
+    network_registry.serialize(&mut *network_registry_info.data.borrow_mut())?;
+
+    bridge_admin.admin_nonce += 1;
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_add_network<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    network: Network,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let (bridge_admin, mut network_registry) = load_network_registry(program_id, bridge_admin_info, network_registry_info, seeds)?;
+
+    let content = OperationContent::new(
+        network_registry.nonce,
+        *bridge_admin_info.key,
+        OperationType::AddNetwork,
+        network.get_operation(),
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    if network_registry.find(&network.name).is_some() {
+        return Err(LibError::AlreadyInUse.into());
+    }
+
+    if network_registry.networks.len() >= MAX_NETWORKS_COUNT {
+        return Err(LibError::WrongArgsSize.into());
+    }
+
+    network_registry.nonce += 1;
+    network_registry.networks.push(network);
+    network_registry.serialize(&mut *network_registry_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_remove_network<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    network: Network,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let network_registry_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let (bridge_admin, mut network_registry) = load_network_registry(program_id, bridge_admin_info, network_registry_info, seeds)?;
+
+    let content = OperationContent::new(
+        network_registry.nonce,
+        *bridge_admin_info.key,
+        OperationType::RemoveNetwork,
+        network.get_operation(),
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    let len = network_registry.networks.len();
+    network_registry.networks.retain(|n| n.name != network.name);
+    if network_registry.networks.len() == len {
+        return Err(LibError::UnknownNetwork.into());
+    }
+
+    network_registry.nonce += 1;
+    network_registry.serialize(&mut *network_registry_info.data.borrow_mut())?;
+    Ok(())
+}
+
+fn load_network_registry<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    network_registry_info: &AccountInfo<'a>,
+    seeds: [u8; 32],
+) -> Result<(BridgeAdmin, NetworkRegistry), ProgramError> {
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let network_registry = get_network_registry(program_id, bridge_admin_info, network_registry_info)?;
+    Ok((bridge_admin, network_registry))
+}
+
+fn get_network_registry<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    network_registry_info: &AccountInfo<'a>,
+) -> Result<NetworkRegistry, ProgramError> {
+    check_network_registry_address(program_id, bridge_admin_info, network_registry_info)?;
+
+    let network_registry: NetworkRegistry = BorshDeserialize::deserialize(&mut network_registry_info.data.borrow_mut().as_ref())?;
+    if !network_registry.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    Ok(network_registry)
+}
+
+fn check_network_registry_address<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    network_registry_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (network_registry_key, _) = Pubkey::find_program_address(
+        &[lib::NETWORK_REGISTRY_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()],
+        program_id,
+    );
+    if network_registry_key != *network_registry_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    Ok(())
+}
+
+// Checks that destination network is registered and receiver has the network address format.
+// Until the registry is created after the program upgrade, destination networks are not validated.
+fn verify_network<'a>(
+    program_id: &Pubkey,
+    bridge_admin_info: &AccountInfo<'a>,
+    network_registry_info: &AccountInfo<'a>,
+    network: &str,
+    receiver: &str,
+) -> ProgramResult {
+    if network_registry_info.data_is_empty() {
+        return check_network_registry_address(program_id, bridge_admin_info, network_registry_info);
+    }
+
+    let network_registry = get_network_registry(program_id, bridge_admin_info, network_registry_info)?;
+
+    match network_registry.find(network) {
+        Some(n) => n.validate_receiver(receiver),
+        None => Err(LibError::UnknownNetwork.into()),
+    }
+}
+
+pub fn verify_commission_charged<'a>(
+    bridge_admin_info: &AccountInfo<'a>,
+    instruction_sysvar_info: &AccountInfo<'a>,
+    admin: &BridgeAdmin,
+    token: lib::TokenType,
+    amount: u64,
+) -> ProgramResult {
+    let current_index = load_current_index_checked(instruction_sysvar_info)?;
+    let commission_instruction = load_instruction_at_checked((current_index - 1) as usize, instruction_sysvar_info)?;
+
+    if commission_instruction.program_id != admin.commission_program {
+        return Err(LibError::WrongCommissionProgram.into());
+    }
+
+    let commission_key = Pubkey::create_program_address(&[lib::COMMISSION_ADMIN_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()], &commission_instruction.program_id)?;
+    if commission_key != commission_instruction.accounts[0].pubkey {
+        return Err(LibError::WrongCommissionAccount.into());
+    }
+
+    let instruction = lib::instructions::commission::CommissionInstruction::try_from_slice(commission_instruction.data.as_slice())?;
+
+    if let lib::instructions::commission::CommissionInstruction::ChargeCommission(args) = instruction {
+        if args.deposit_token == token && args.deposit_token_amount == amount {
+            return Ok(());
         }
     }
 
-    return Err(LibError::WrongCommissionArguments.into());
+    return Err(LibError::WrongCommissionArguments.into());
+}
+
+pub fn process_create_collection<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    data: SignedMetadata,
+    token_seed: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+
+    let mint_info = next_account_info(account_info_iter)?;
+    let bridge_associated_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+
+    let payer_info = next_account_info(account_info_iter)?;
+
+    let token_program = next_account_info(account_info_iter)?;
+    let metadata_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let _associated_program = next_account_info(account_info_iter)?;
+    let edition_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    verify_token_program(token_program)?;
+
+    if *bridge_associated_info.key !=
+        get_associated_token_address_with_program_id(&bridge_admin_key, mint_info.key, token_program.key) {
+        return Err(LibError::WrongTokenAccount.into());
+    }
+
+    let (mint_key, bump_seed) = Pubkey::find_program_address(&[token_seed.as_slice()], program_id);
+    if mint_key != *mint_info.key {
+        return Err(LibError::WrongTokenSeed.into());
+    }
+
+    if *metadata_info.key != Metadata::find_pda(mint_info.key).0 || *edition_info.key != MasterEdition::find_pda(mint_info.key).0 {
+        return Err(LibError::WrongMetadataAccount.into());
+    }
+
+    msg!("Creating mint account");
+    lib::call_create_account(
+        payer_info,
+        mint_info,
+        rent_info,
+        system_program,
+        Mint::LEN,
+        token_program.key,
+        &[token_seed.as_slice(), &[bump_seed]],
+    )?;
+
+    msg!("Initializing mint account");
+    call_init_mint(
+        token_program,
+        mint_info,
+        bridge_admin_info,
+        rent_info,
+        0,
+    )?;
+
+    msg!("Crating bridge admin associated account");
+    lib::call_create_associated_account(
+        payer_info,
+        bridge_admin_info,
+        mint_info,
+        bridge_associated_info,
+        rent_info,
+        system_program,
+        token_program,
+    )?;
+
+    msg!("Minting token to bridge admin");
+    call_mint_to(
+        token_program,
+        mint_info,
+        bridge_associated_info,
+        bridge_admin_info,
+        seeds,
+        1,
+    )?;
+
+    msg!("Creating metadata account");
+    call_create_metadata(
+        metadata_info,
+        mint_info,
+        bridge_admin_info,
+        payer_info,
+        bridge_admin_info,
+        rent_info,
+        system_program,
+        data,
+        seeds,
+    )?;
+
+    // Collection items can be verified only with the master edition
+    msg!("Creating master edition");
+    call_create_master_edition(
+        metadata_program,
+        token_program,
+        system_program,
+        rent_info,
+        mint_info,
+        metadata_info,
+        edition_info,
+        bridge_admin_info,
+        payer_info,
+        seeds,
+    )?;
+
+    Ok(())
+}
+
+pub fn process_update_wrapped_metadata<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    token_seed: [u8; 32],
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Vec<SignedCreator>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let metadata_nonce_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let metadata_program = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let (mint_key, _) = Pubkey::find_program_address(&[token_seed.as_slice()], program_id);
+    if mint_key != *mint_info.key {
+        return Err(LibError::WrongTokenSeed.into());
+    }
+
+    if *metadata_info.key != Metadata::find_pda(mint_info.key).0 {
+        return Err(LibError::WrongMetadataAccount.into());
+    }
+
+    let metadata: mpl_token_metadata::accounts::Metadata = Metadata::from_bytes(&metadata_info.data.borrow())?;
+    if !metadata.is_mutable {
+        return Err(LibError::ImmutableMetadata.into());
+    }
+
+    let (metadata_nonce_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::METADATA_NONCE_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), mint_key.as_ref()],
+        program_id,
+    );
+    if metadata_nonce_key != *metadata_nonce_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    // The signature is verified before the account is created, the first operation has zero nonce
+    let nonce = if metadata_nonce_info.data_is_empty() {
+        0
+    } else {
+        let metadata_nonce: MetadataNonce = BorshDeserialize::deserialize(&mut metadata_nonce_info.data.borrow().as_ref())?;
+        metadata_nonce.nonce
+    };
+
+    let mut data = Vec::from(mint_key.to_bytes());
+    data.append(&mut Vec::from(name.as_bytes()));
+    data.append(&mut Vec::from(symbol.as_bytes()));
+    data.append(&mut Vec::from(uri.as_bytes()));
+    data.append(&mut royalties_bytes(seller_fee_basis_points, &creators));
+
+    let content = OperationContent::new(
+        nonce,
+        bridge_admin_key,
+        OperationType::UpdateWrappedMetadata,
+        data,
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    if metadata_nonce_info.data_is_empty() {
+        msg!("Creating metadata nonce account");
+        lib::call_create_account(
+            fee_payer_info,
+            metadata_nonce_info,
+            rent_info,
+            system_program,
+            METADATA_NONCE_SIZE,
+            program_id,
+            &[lib::METADATA_NONCE_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), mint_key.as_ref(), &[bump_seed]],
+        )?;
+    }
+
+    let mut metadata_nonce: MetadataNonce = BorshDeserialize::deserialize(&mut metadata_nonce_info.data.borrow_mut().as_ref())?;
+
+    msg!("Updating metadata");
+    call_update_metadata(
+        metadata_program,
+        metadata_info,
+        bridge_admin_info,
+        DataV2 {
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points,
+            creators: get_creators(creators),
+            collection: metadata.collection,
+            uses: metadata.uses,
+        },
+        seeds,
+    )?;
+
+    metadata_nonce.is_initialized = true;
+    metadata_nonce.mint = mint_key;
+    metadata_nonce.nonce += 1;
+    metadata_nonce.serialize(&mut *metadata_nonce_info.data.borrow_mut())?;
+    Ok(())
 }
 
-pub fn process_create_collection<'a>(
+pub fn process_migrate_admin<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
     seeds: [u8; 32],
-    data: SignedMetadata,
-    token_seed: [u8; 32],
+    signature: EcdsaSignature,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    let bridge_admin_info = next_account_info(account_info_iter)?;
-
-    let mint_info = next_account_info(account_info_iter)?;
-    let bridge_associated_info = next_account_info(account_info_iter)?;
-    let metadata_info = next_account_info(account_info_iter)?;
-
-    let payer_info = next_account_info(account_info_iter)?;
 
-    let token_program = next_account_info(account_info_iter)?;
-    let _metadata_program = next_account_info(account_info_iter)?;
-    let rent_info = next_account_info(account_info_iter)?;
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
-    let _associated_program = next_account_info(account_info_iter)?;
 
-    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], &program_id)?;
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
     if *bridge_admin_info.key != bridge_admin_key {
         return Err(LibError::WrongSeeds.into());
     }
 
-    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
-    if !bridge_admin.is_initialized {
+    if bridge_admin_info.data_len() != LEGACY_BRIDGE_ADMIN_SIZE {
+        return Err(LibError::AlreadyMigrated.into());
+    }
+
+    let legacy: LegacyBridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow().as_ref())?;
+    if !legacy.is_initialized {
         return Err(LibError::NotInitialized.into());
     }
 
-    if *bridge_associated_info.key !=
-        get_associated_token_address(&bridge_admin_key, mint_info.key) {
-        return Err(LibError::WrongTokenAccount.into());
+    // The legacy account has no nonces, the migration can be done only once because the size changes
+    let content = OperationContent::new(
+        0,
+        bridge_admin_key,
+        OperationType::MigrateAdmin,
+        vec![],
+    );
+    verify_ecdsa_signature(content.hash().as_ref(), signature.signature.as_slice(), signature.recovery_id, legacy.public_key)?;
+
+    // Lamports above the rent-exempt reserve are the native liquidity, so the fee payer covers the new size
+    let rent = Rent::get()?;
+    let rent_diff = rent.minimum_balance(BRIDGE_ADMIN_SIZE) - rent.minimum_balance(LEGACY_BRIDGE_ADMIN_SIZE);
+
+    msg!("Paying rent for the new admin size");
+    invoke(
+        &system_instruction::transfer(fee_payer_info.key, bridge_admin_info.key, rent_diff),
+        &[
+            fee_payer_info.clone(),
+            bridge_admin_info.clone(),
+            system_program.clone(),
+        ],
+    )?;
+
+    bridge_admin_info.realloc(BRIDGE_ADMIN_SIZE, true)?;
+
+    let bridge_admin = BridgeAdmin {
+        signers: SignerScheme::Secp256k1(SignerSet {
+            public_keys: vec![legacy.public_key],
+            threshold: 1,
+        }),
+        commission_program: legacy.commission_program,
+        deposit_nonce: 0,
+        message_nonce: 0,
+        pause: PauseState::default(),
+        admin_nonce: 0,
+        withdraw_mode: WithdrawMode::default(),
+        is_initialized: true,
+    };
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+    Ok(())
+}
+
+// Native liquidity was kept in the BridgeAdmin account before the SOL vault was introduced.
+// The BridgeAdmin should be migrated first, so its rent-exempt reserve is for the current size.
+pub fn process_migrate_sol_vault<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let sol_vault_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
     }
 
-    let (mint_key, _) = Pubkey::find_program_address(&[token_seed.as_slice()], program_id);
-    if mint_key != *mint_info.key {
-        return Err(LibError::WrongTokenSeed.into());
+    if bridge_admin_info.data_len() == LEGACY_BRIDGE_ADMIN_SIZE {
+        return Err(LibError::NotMigrated.into());
     }
 
-    msg!("Creating mint account");
-    lib::call_create_account(
-        payer_info,
-        mint_info,
-        rent_info,
-        system_program,
-        Mint::LEN,
-        &spl_token::id(),
-        &[],
-    )?;
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
 
-    msg!("Initializing mint account");
-    call_init_mint(
-        mint_info,
-        bridge_admin_info,
-        rent_info,
-        0,
-    )?;
+    let content = OperationContent::new(
+        bridge_admin.admin_nonce,
+        bridge_admin_key,
+        OperationType::MigrateSolVault,
+        vec![],
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
 
-    msg!("Crating bridge admin associated account");
-    lib::call_create_associated_account(
-        payer_info,
-        bridge_admin_info,
-        mint_info,
-        bridge_associated_info,
-        rent_info,
-        system_program,
-        token_program,
-    )?;
+    verify_sol_vault(program_id, &bridge_admin_key, sol_vault_info)?;
 
-    msg!("Minting token to bridge admin");
-    call_mint_to(
-        mint_info,
-        bridge_associated_info,
-        bridge_admin_info,
-        seeds,
-        1,
-    )?;
+    let rent = Rent::get()?;
+    let reserve = rent.minimum_balance(bridge_admin_info.data_len());
+    let excess = bridge_admin_info.lamports().saturating_sub(reserve);
 
-    msg!("Creating metadata account");
-    call_create_metadata(
-        metadata_info,
-        mint_info,
-        bridge_admin_info,
-        payer_info,
-        bridge_admin_info,
-        rent_info,
-        system_program,
-        data,
-        seeds,
-    )?;
+    // The vault is created by the transfer, lamports below its rent-exempt reserve would fail the transaction
+    if sol_vault_info.lamports() + excess < rent.minimum_balance(0) {
+        return Err(LibError::InsufficientVaultRent.into());
+    }
+
+    msg!("Moving {} lamports to SOL vault", excess);
+    if excess > 0 {
+        **bridge_admin_info.try_borrow_mut_lamports()? -= excess;
+        **sol_vault_info.try_borrow_mut_lamports()? += excess;
+    }
 
+    bridge_admin.admin_nonce += 1;
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
     Ok(())
 }
 
@@ -949,9 +3214,10 @@ fn try_mint_token_with_meta<'a>(
     signed_meta: Option<SignedMetadata>,
     mint_info: &AccountInfo<'a>,
     metadata_info: &AccountInfo<'a>,
-    owner_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
     rent_info: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
     seeds: [u8; 32],
 ) -> ProgramResult {
     let (mint_key, bump_seed) = Pubkey::find_program_address(&[token_seed.as_slice()], program_id);
@@ -970,17 +3236,18 @@ fn try_mint_token_with_meta<'a>(
     if mint_info.data.borrow().as_ref().len() == 0 {
         msg!("Creating mint account");
         lib::call_create_account(
-            owner_info,
+            payer_info,
             mint_info,
             rent_info,
             system_program,
             Mint::LEN,
-            &spl_token::id(),
+            token_program.key,
             &[token_seed.as_slice(), &[bump_seed]],
         )?;
 
         msg!("Initializing mint account");
         call_init_mint(
+            token_program,
             mint_info,
             bridge_admin_info,
             rent_info,
@@ -992,7 +3259,7 @@ fn try_mint_token_with_meta<'a>(
             metadata_info,
             mint_info,
             bridge_admin_info,
-            owner_info,
+            payer_info,
             bridge_admin_info,
             rent_info,
             system_program,
@@ -1001,55 +3268,506 @@ fn try_mint_token_with_meta<'a>(
         )?;
     }
 
-    Ok(())
+    Ok(())
+}
+
+
+// Mint extensions that do not affect bridge transfers
+const SUPPORTED_MINT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+fn verify_token_program(token_program: &AccountInfo) -> ProgramResult {
+    if *token_program.key != spl_token::id() && *token_program.key != spl_token_2022::id() {
+        return Err(LibError::WrongTokenProgram.into());
+    }
+
+    Ok(())
+}
+
+// Checks that the mint belongs to the token program and has only supported extensions
+fn load_mint(token_program: &AccountInfo, mint_info: &AccountInfo) -> Result<Mint, ProgramError> {
+    if *mint_info.owner != *token_program.key {
+        return Err(LibError::WrongTokenProgram.into());
+    }
+
+    let data = mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    for extension in mint.get_extension_types()? {
+        if !SUPPORTED_MINT_EXTENSIONS.contains(&extension) {
+            msg!("Unsupported mint extension: {:?}", extension);
+            return Err(LibError::UnsupportedMintExtension.into());
+        }
+    }
+
+    Ok(mint.base)
+}
+
+fn token_account_amount(account_info: &AccountInfo) -> Result<u64, ProgramError> {
+    let data = account_info.data.borrow();
+    Ok(StateWithExtensions::<Account>::unpack(&data)?.base.amount)
+}
+
+fn verify_sol_vault(program_id: &Pubkey, bridge_admin_key: &Pubkey, sol_vault_info: &AccountInfo) -> Result<u8, ProgramError> {
+    let (sol_vault_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::SOL_VAULT_PDA_SEED.as_bytes(), bridge_admin_key.as_ref()],
+        program_id,
+    );
+    if sol_vault_key != *sol_vault_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    Ok(bump_seed)
+}
+
+// The vault of a legacy bridge stays empty until its SOL is moved from the BridgeAdmin account by MigrateSolVault
+fn verify_sol_vault_migrated(program_id: &Pubkey, bridge_admin_key: &Pubkey, sol_vault_info: &AccountInfo) -> Result<u8, ProgramError> {
+    let bump_seed = verify_sol_vault(program_id, bridge_admin_key, sol_vault_info)?;
+    if sol_vault_info.lamports() < Rent::get()?.minimum_balance(0) {
+        return Err(LibError::NotMigrated.into());
+    }
+
+    Ok(bump_seed)
+}
+
+// wSOL is supported only for the native mint of the SPL Token program
+fn verify_native_associated(owner_info: &AccountInfo, associated_info: &AccountInfo, token_program: &AccountInfo) -> ProgramResult {
+    if *token_program.key != spl_token::id() {
+        return Err(LibError::WrongTokenProgram.into());
+    }
+
+    if *associated_info.key !=
+        get_associated_token_address_with_program_id(owner_info.key, &spl_token::native_mint::id(), token_program.key) {
+        return Err(LibError::WrongTokenAccount.into());
+    }
+
+    Ok(())
+}
+
+fn call_burn_token<'a>(
+    token_program: &AccountInfo<'a>,
+    associated_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    authority_info: &AccountInfo<'a>,
+    amount: u64,
+    decimals: u8,
+) -> ProgramResult {
+    let burn_tokens_instruction = burn_checked(
+        token_program.key,
+        associated_info.key,
+        mint_info.key,
+        authority_info.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+
+    invoke(
+        &burn_tokens_instruction,
+        &[
+            associated_info.clone(),
+            mint_info.clone(),
+            authority_info.clone(),
+        ],
+    )
+}
+
+// Accounts of the Metaplex transfer, passed after the token metadata if the token is programmable NFT
+struct ProgrammableAccounts<'a> {
+    edition: &'a AccountInfo<'a>,
+    owner_token_record: &'a AccountInfo<'a>,
+    bridge_token_record: &'a AccountInfo<'a>,
+    metadata_program: &'a AccountInfo<'a>,
+    sysvar_instructions: &'a AccountInfo<'a>,
+    // Metadata program account if the token has no rule set
+    authorization_rules_program: &'a AccountInfo<'a>,
+    authorization_rules: &'a AccountInfo<'a>,
+}
+
+impl<'a> ProgrammableAccounts<'a> {
+    fn next(account_info_iter: &mut Iter<'a, AccountInfo<'a>>, metadata: &Metadata) -> Result<Option<Self>, ProgramError> {
+        match metadata.token_standard {
+            Some(TokenStandard::ProgrammableNonFungible) | Some(TokenStandard::ProgrammableNonFungibleEdition) => Ok(Some(Self {
+                edition: next_account_info(account_info_iter)?,
+                owner_token_record: next_account_info(account_info_iter)?,
+                bridge_token_record: next_account_info(account_info_iter)?,
+                metadata_program: next_account_info(account_info_iter)?,
+                sysvar_instructions: next_account_info(account_info_iter)?,
+                authorization_rules_program: next_account_info(account_info_iter)?,
+                authorization_rules: next_account_info(account_info_iter)?,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    // Programmable NFTs are frozen in token accounts, so they can be moved only by the Metaplex transfer
+    fn call_transfer(
+        &self,
+        token_program: &AccountInfo<'a>,
+        associated_program: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        mint: &AccountInfo<'a>,
+        metadata: &AccountInfo<'a>,
+        from: &AccountInfo<'a>,
+        from_owner: &AccountInfo<'a>,
+        from_record: &AccountInfo<'a>,
+        to: &AccountInfo<'a>,
+        to_owner: &AccountInfo<'a>,
+        to_record: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let transfer = TransferV1 {
+            token: *from.key,
+            token_owner: *from_owner.key,
+            destination_token: *to.key,
+            destination_owner: *to_owner.key,
+            mint: *mint.key,
+            metadata: *metadata.key,
+            edition: Some(*self.edition.key),
+            token_record: Some(*from_record.key),
+            destination_token_record: Some(*to_record.key),
+            authority: *from_owner.key,
+            payer: *payer.key,
+            system_program: *system_program.key,
+            sysvar_instructions: *self.sysvar_instructions.key,
+            spl_token_program: *token_program.key,
+            spl_ata_program: *associated_program.key,
+            authorization_rules_program: Some(*self.authorization_rules_program.key),
+            authorization_rules: Some(*self.authorization_rules.key),
+        };
+
+        invoke_signed(
+            &transfer.instruction(TransferV1InstructionArgs {
+                amount: 1,
+                authorization_data: None,
+            }),
+            &[
+                from.clone(),
+                from_owner.clone(),
+                to.clone(),
+                to_owner.clone(),
+                mint.clone(),
+                metadata.clone(),
+                self.edition.clone(),
+                from_record.clone(),
+                to_record.clone(),
+                payer.clone(),
+                system_program.clone(),
+                self.sysvar_instructions.clone(),
+                token_program.clone(),
+                associated_program.clone(),
+                self.authorization_rules_program.clone(),
+                self.authorization_rules.clone(),
+                self.metadata_program.clone(),
+            ],
+            signers_seeds,
+        )
+    }
+}
+
+fn verify_wrapped_asset(
+    program_id: &Pubkey,
+    bridge_admin_key: &Pubkey,
+    wrapped_asset_info: &AccountInfo,
+    token_seed: [u8; 32],
+) -> Result<u8, ProgramError> {
+    let (wrapped_asset_key, bump_seed) = lib::instructions::bridge::get_wrapped_asset_address(program_id, bridge_admin_key, token_seed);
+    if *wrapped_asset_info.key != wrapped_asset_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    Ok(bump_seed)
+}
+
+// Wrapped compressed NFTs are minted only into the tree delegated to the bridge admin
+fn verify_bridge_tree<'a>(
+    bridge_admin_key: &Pubkey,
+    tree_config_info: &AccountInfo<'a>,
+    merkle_tree_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    if *tree_config_info.owner != mpl_bubblegum::ID || *tree_config_info.key != TreeConfig::find_pda(merkle_tree_info.key).0 {
+        return Err(LibError::WrongMerkleTree.into());
+    }
+
+    let tree_config = TreeConfig::from_bytes(&tree_config_info.data.borrow())?;
+    if tree_config.tree_delegate != *bridge_admin_key {
+        return Err(LibError::WrongMerkleTree.into());
+    }
+
+    Ok(())
+}
+
+fn call_transfer_leaf<'a>(
+    bubblegum_program: &AccountInfo<'a>,
+    tree_config: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    delegate: &AccountInfo<'a>,
+    new_owner: &AccountInfo<'a>,
+    merkle_tree: &AccountInfo<'a>,
+    log_wrapper: &AccountInfo<'a>,
+    compression_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    leaf: &CompressedLeaf,
+    proof: &[AccountInfo<'a>],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let transfer = TransferLeaf {
+        tree_config: *tree_config.key,
+        leaf_owner: (*owner.key, true),
+        leaf_delegate: (*delegate.key, false),
+        new_leaf_owner: *new_owner.key,
+        merkle_tree: *merkle_tree.key,
+        log_wrapper: *log_wrapper.key,
+        compression_program: *compression_program.key,
+        system_program: *system_program.key,
+    };
+
+    let proof_metas: Vec<AccountMeta> = proof.iter().map(|info| AccountMeta::new_readonly(*info.key, false)).collect();
+    let instruction = transfer.instruction_with_remaining_accounts(
+        TransferLeafInstructionArgs {
+            root: leaf.root,
+            data_hash: hash_metadata(&leaf.metadata)?,
+            creator_hash: hash_creators(&leaf.metadata.creators),
+            nonce: leaf.nonce,
+            index: leaf.index,
+        },
+        &proof_metas,
+    );
+
+    let mut infos = vec![
+        tree_config.clone(),
+        owner.clone(),
+        delegate.clone(),
+        new_owner.clone(),
+        merkle_tree.clone(),
+        log_wrapper.clone(),
+        compression_program.clone(),
+        system_program.clone(),
+        bubblegum_program.clone(),
+    ];
+    infos.extend_from_slice(proof);
+
+    invoke_signed(&instruction, &infos, signers_seeds)
+}
+
+fn call_burn_leaf<'a>(
+    bubblegum_program: &AccountInfo<'a>,
+    tree_config: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    delegate: &AccountInfo<'a>,
+    merkle_tree: &AccountInfo<'a>,
+    log_wrapper: &AccountInfo<'a>,
+    compression_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    leaf: &CompressedLeaf,
+    proof: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let burn = BurnLeaf {
+        tree_config: *tree_config.key,
+        leaf_owner: (*owner.key, true),
+        leaf_delegate: (*delegate.key, false),
+        merkle_tree: *merkle_tree.key,
+        log_wrapper: *log_wrapper.key,
+        compression_program: *compression_program.key,
+        system_program: *system_program.key,
+    };
+
+    let proof_metas: Vec<AccountMeta> = proof.iter().map(|info| AccountMeta::new_readonly(*info.key, false)).collect();
+    let instruction = burn.instruction_with_remaining_accounts(
+        BurnLeafInstructionArgs {
+            root: leaf.root,
+            data_hash: hash_metadata(&leaf.metadata)?,
+            creator_hash: hash_creators(&leaf.metadata.creators),
+            nonce: leaf.nonce,
+            index: leaf.index,
+        },
+        &proof_metas,
+    );
+
+    let mut infos = vec![
+        tree_config.clone(),
+        owner.clone(),
+        delegate.clone(),
+        merkle_tree.clone(),
+        log_wrapper.clone(),
+        compression_program.clone(),
+        system_program.clone(),
+        bubblegum_program.clone(),
+    ];
+    infos.extend_from_slice(proof);
+
+    invoke(&instruction, &infos)
+}
+
+fn call_mint_leaf<'a>(
+    bubblegum_program: &AccountInfo<'a>,
+    tree_config: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    merkle_tree: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    bridge_admin: &AccountInfo<'a>,
+    log_wrapper: &AccountInfo<'a>,
+    compression_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    metadata: MetadataArgs,
+    seeds: [u8; 32],
+) -> ProgramResult {
+    let mint = MintV1 {
+        tree_config: *tree_config.key,
+        leaf_owner: *owner.key,
+        leaf_delegate: *owner.key,
+        merkle_tree: *merkle_tree.key,
+        payer: *payer.key,
+        tree_creator_or_delegate: *bridge_admin.key,
+        log_wrapper: *log_wrapper.key,
+        compression_program: *compression_program.key,
+        system_program: *system_program.key,
+    };
+
+    invoke_signed(
+        &mint.instruction(MintV1InstructionArgs { metadata }),
+        &[
+            tree_config.clone(),
+            owner.clone(),
+            merkle_tree.clone(),
+            payer.clone(),
+            bridge_admin.clone(),
+            log_wrapper.clone(),
+            compression_program.clone(),
+            system_program.clone(),
+            bubblegum_program.clone(),
+        ],
+        &[&[&seeds]],
+    )
 }
 
+// Master edition with zero max supply moves the mint authority to the edition, so the supply stays 1
+fn call_create_master_edition<'a>(
+    metadata_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    metadata: &AccountInfo<'a>,
+    edition: &AccountInfo<'a>,
+    bridge_admin: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    seeds: [u8; 32],
+) -> ProgramResult {
+    let create_edition = CreateMasterEditionV3 {
+        edition: *edition.key,
+        mint: *mint.key,
+        update_authority: *bridge_admin.key,
+        mint_authority: *bridge_admin.key,
+        payer: *payer.key,
+        metadata: *metadata.key,
+        token_program: *token_program.key,
+        system_program: *system_program.key,
+        rent: Some(*rent_info.key),
+    };
 
-fn call_burn_token<'a>(
-    associated_info: &AccountInfo<'a>,
-    mint_info: &AccountInfo<'a>,
-    authority_info: &AccountInfo<'a>,
-    amount: u64,
+    invoke_signed(
+        &create_edition.instruction(CreateMasterEditionV3InstructionArgs { max_supply: Some(0) }),
+        &[
+            edition.clone(),
+            mint.clone(),
+            bridge_admin.clone(),
+            payer.clone(),
+            metadata.clone(),
+            token_program.clone(),
+            system_program.clone(),
+            rent_info.clone(),
+            metadata_program.clone(),
+        ],
+        &[&[&seeds]],
+    )
+}
+
+// Verifies the token in the collection created by MintCollection, the bridge admin is its update authority
+fn call_verify_collection<'a>(
+    metadata_program: &AccountInfo<'a>,
+    metadata: &AccountInfo<'a>,
+    bridge_admin: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    collection_mint: &AccountInfo<'a>,
+    collection_metadata: &AccountInfo<'a>,
+    collection_edition: &AccountInfo<'a>,
+    sized: bool,
+    seeds: [u8; 32],
 ) -> ProgramResult {
-    let burn_tokens_instruction = burn(
-        &spl_token::id(),
-        associated_info.key,
-        mint_info.key,
-        authority_info.key,
-        &[],
-        amount,
-    )?;
+    let instruction = if sized {
+        VerifySizedCollectionItem {
+            metadata: *metadata.key,
+            collection_authority: *bridge_admin.key,
+            payer: *payer.key,
+            collection_mint: *collection_mint.key,
+            collection: *collection_metadata.key,
+            collection_master_edition_account: *collection_edition.key,
+            collection_authority_record: None,
+        }.instruction()
+    } else {
+        VerifyCollection {
+            metadata: *metadata.key,
+            collection_authority: *bridge_admin.key,
+            payer: *payer.key,
+            collection_mint: *collection_mint.key,
+            collection: *collection_metadata.key,
+            collection_master_edition_account: *collection_edition.key,
+            collection_authority_record: None,
+        }.instruction()
+    };
 
-    invoke(
-        &burn_tokens_instruction,
+    invoke_signed(
+        &instruction,
         &[
-            associated_info.clone(),
-            mint_info.clone(),
-            authority_info.clone(),
+            metadata.clone(),
+            bridge_admin.clone(),
+            payer.clone(),
+            collection_mint.clone(),
+            collection_metadata.clone(),
+            collection_edition.clone(),
+            metadata_program.clone(),
         ],
+        &[&[&seeds]],
     )
 }
 
 fn call_transfer_token<'a>(
+    token_program: &AccountInfo<'a>,
     from: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
     to: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
     amount: u64,
+    decimals: u8,
     signers_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    let transfer_tokens_instruction = transfer(
-        &spl_token::id(),
+    let transfer_tokens_instruction = transfer_checked(
+        token_program.key,
         from.key,
+        mint.key,
         to.key,
         authority.key,
         &[],
         amount,
+        decimals,
     )?;
 
     invoke_signed(
         &transfer_tokens_instruction,
         &[
             from.clone(),
+            mint.clone(),
             to.clone(),
             authority.clone(),
         ],
@@ -1058,6 +3776,7 @@ fn call_transfer_token<'a>(
 }
 
 fn call_mint_to<'a>(
+    token_program: &AccountInfo<'a>,
     mint: &AccountInfo<'a>,
     account: &AccountInfo<'a>,
     owner: &AccountInfo<'a>,
@@ -1065,7 +3784,7 @@ fn call_mint_to<'a>(
     amount: u64,
 ) -> ProgramResult {
     let mint_to_instruction = mint_to(
-        &spl_token::id(),
+        token_program.key,
         mint.key,
         account.key,
         owner.key,
@@ -1084,14 +3803,72 @@ fn call_mint_to<'a>(
     )
 }
 
+fn call_transfer_sol<'a>(
+    system_program: &AccountInfo<'a>,
+    from: &AccountInfo<'a>,
+    to: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::transfer(from.key, to.key, amount),
+        &[
+            from.clone(),
+            to.clone(),
+            system_program.clone(),
+        ],
+        &[signer_seeds],
+    )
+}
+
+fn call_close_account<'a>(
+    token_program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+) -> ProgramResult {
+    let close_account_instruction = close_account(
+        token_program.key,
+        account.key,
+        destination.key,
+        owner.key,
+        &[],
+    )?;
+
+    invoke(
+        &close_account_instruction,
+        &[
+            account.clone(),
+            destination.clone(),
+            owner.clone(),
+        ],
+    )
+}
+
+fn call_sync_native<'a>(
+    token_program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+) -> ProgramResult {
+    let sync_native_instruction = sync_native(
+        token_program.key,
+        account.key,
+    )?;
+
+    invoke(
+        &sync_native_instruction,
+        std::slice::from_ref(account),
+    )
+}
+
 fn call_init_mint<'a>(
+    token_program: &AccountInfo<'a>,
     mint: &AccountInfo<'a>,
     mint_authority: &AccountInfo<'a>,
     rent: &AccountInfo<'a>,
     decimals: u8,
 ) -> ProgramResult {
     let init_mint_instruction = initialize_mint(
-        &spl_token::id(),
+        token_program.key,
         mint.key,
         mint_authority.key,
         None,
@@ -1138,17 +3915,22 @@ fn call_create_metadata<'a>(
         None,
     );
     */
+    let (seller_fee_basis_points, creators) = match data.royalties {
+        Some(royalties) => (royalties.seller_fee_basis_points, get_creators(royalties.creators)),
+        None => (0, None),
+    };
+
     let args = CreateMetadataAccountV3InstructionArgs{
         data: DataV2{
             name: data.name,
             symbol: data.symbol,
             uri: data.uri,
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
+            seller_fee_basis_points,
+            creators,
+            collection: data.collection.map(|key| Collection { verified: false, key }),
             uses: None,
         },
-        is_mutable: false,
+        is_mutable: true,
         collection_details: None,
     };
    
@@ -1180,3 +3962,580 @@ fn call_create_metadata<'a>(
         &[&[&seeds]],
     )
 }
+
+fn call_update_metadata<'a>(
+    metadata_program: &AccountInfo<'a>,
+    metadata: &AccountInfo<'a>,
+    update_authority: &AccountInfo<'a>,
+    data: DataV2,
+    seeds: [u8; 32],
+) -> ProgramResult {
+    let update_metadata = UpdateMetadataAccountV2 {
+        metadata: *metadata.key,
+        update_authority: *update_authority.key,
+    };
+
+    invoke_signed(
+        &update_metadata.instruction(UpdateMetadataAccountV2InstructionArgs {
+            data: Some(data),
+            new_update_authority: None,
+            primary_sale_happened: None,
+            is_mutable: None,
+        }),
+        &[
+            metadata.clone(),
+            update_authority.clone(),
+            metadata_program.clone(),
+        ],
+        &[&[&seeds]],
+    )
+}
+
+// Creators from other networks can not sign, so they are never verified
+fn get_creators(creators: Vec<SignedCreator>) -> Option<Vec<Creator>> {
+    if creators.is_empty() {
+        return None;
+    }
+
+    Some(creators.into_iter().map(|creator| Creator {
+        address: creator.address,
+        verified: false,
+        share: creator.share,
+    }).collect())
+}
+
+pub fn process_submit_root<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    root: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let signed_root_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let (signed_root_key, bump_seed) = Pubkey::find_program_address(
+        &[lib::SIGNED_ROOT_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), root.as_slice()],
+        program_id,
+    );
+    if signed_root_key != *signed_root_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    if !signed_root_info.data_is_empty() {
+        return Err(LibError::AlreadyInUse.into());
+    }
+
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    msg!("Creating signed root account");
+    lib::call_create_account(
+        fee_payer_info,
+        signed_root_info,
+        rent_info,
+        system_program,
+        SIGNED_ROOT_SIZE,
+        program_id,
+        &[lib::SIGNED_ROOT_PDA_SEED.as_bytes(), bridge_admin_key.as_ref(), root.as_slice(), &[bump_seed]],
+    )?;
+
+    let signed_root = SignedRoot {
+        bridge_admin: bridge_admin_key,
+        root,
+        signers_hash: bridge_admin.signers.hash()?,
+        slot: Clock::get()?.slot,
+        bump: bump_seed,
+        is_initialized: true,
+    };
+    signed_root.serialize(&mut *signed_root_info.data.borrow_mut())?;
+    Ok(())
+}
+pub fn process_set_withdraw_mode<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    mode: WithdrawMode,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    // Withdraw accounts can be closed in the bitmap mode, so the receipt mode can not be enabled again
+    if bridge_admin.withdraw_mode == WithdrawMode::Bitmap && mode != WithdrawMode::Bitmap {
+        return Err(LibError::WrongWithdrawMode.into());
+    }
+
+    let content = OperationContent::new(
+        bridge_admin.admin_nonce,
+        bridge_admin_key,
+        OperationType::SetWithdrawMode,
+        vec![(&mode).into()],
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    msg!("Withdraw mode: {:?}", mode);
+
+    bridge_admin.withdraw_mode = mode;
+    bridge_admin.admin_nonce += 1;
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_close_withdraw<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+    origin: [u8; 32],
+    bitmap_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let bridge_admin_info = next_account_info(account_info_iter)?;
+    let withdraw_info = next_account_info(account_info_iter)?;
+    let bitmap_info = next_account_info(account_info_iter)?;
+    let rent_payer_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
+
+    let bridge_admin_key = Pubkey::create_program_address(&[&seeds], program_id)?;
+    if *bridge_admin_info.key != bridge_admin_key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    let mut bridge_admin: BridgeAdmin = BorshDeserialize::deserialize(&mut bridge_admin_info.data.borrow_mut().as_ref())?;
+    if !bridge_admin.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    if bridge_admin.withdraw_mode != WithdrawMode::Bitmap {
+        return Err(LibError::WrongWithdrawMode.into());
+    }
+
+    let (withdraw_key, _) = Pubkey::find_program_address(&[origin.as_slice()], program_id);
+    if withdraw_key != *withdraw_info.key {
+        return Err(LibError::WrongNonce.into());
+    }
+
+    // Message receipts share the address space with withdrawals
+    if withdraw_info.data_len() != WITHDRAW_SIZE && withdraw_info.data_len() != LEGACY_WITHDRAW_SIZE {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let withdraw: Withdraw = BorshDeserialize::deserialize(&mut withdraw_info.data.borrow().as_ref())?;
+    if !withdraw.is_initialized || withdraw.origin != origin {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let rent_payer = if withdraw_info.data_len() == LEGACY_WITHDRAW_SIZE {
+        withdraw.receiver_address
+    } else {
+        withdraw.payer
+    };
+
+    if rent_payer != *rent_payer_info.key {
+        return Err(LibError::WrongRentPayer.into());
+    }
+
+    // Withdraw account in PDA(origin) can be created again (e.g. by a message receipt) after closing
+    let mut data = Vec::from(origin.as_slice());
+    data.append(&mut amount_bytes(bitmap_index));
+
+    let content = OperationContent::new(
+        bridge_admin.admin_nonce,
+        bridge_admin_key,
+        OperationType::CloseWithdraw,
+        data,
+    );
+    let root = get_merkle_root(content.hash(), &path)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
+
+    set_withdraw_bit(program_id, &bridge_admin_key, bitmap_info, fee_payer_info, rent_info, system_program, bitmap_index)?;
+
+    bridge_admin.admin_nonce += 1;
+    bridge_admin.serialize(&mut *bridge_admin_info.data.borrow_mut())?;
+
+    msg!("Closing withdraw account");
+    let lamports = withdraw_info.lamports();
+    **withdraw_info.try_borrow_mut_lamports()? = 0;
+    **rent_payer_info.try_borrow_mut_lamports()? += lamports;
+    withdraw_info.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{invoked, TestAccounts, ACCOUNT_ALREADY_IN_USE, PROGRAM_ID, SEEDS};
+    use lib::instructions::bridge::{deposit_cnft, deposit_native, deposit_nft, get_call_authority_address, get_sol_vault_address, get_wrapped_asset_address, initialize_admin, withdraw_and_call, withdraw_batch, withdraw_cnft, withdraw_ft, withdraw_native, withdraw_nft, TargetAccount};
+
+    const OWNER_BALANCE: u64 = 1_000_000_000;
+
+    // Native withdrawal of 1000 lamports calling the target program with one writable account
+    fn withdraw_and_call_fixture() -> (TestAccounts, Instruction, Pubkey) {
+        let mut accounts = TestAccounts::new();
+        let bridge_admin_key = accounts.add_bridge_admin(Pubkey::new_unique());
+        accounts.add_sol_vault(&bridge_admin_key, 1_000_000_000);
+
+        let owner = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        let target_program = Pubkey::new_unique();
+        accounts.add_wallet(owner, OWNER_BALANCE);
+        accounts.add_wallet(fee_payer, 1_000_000_000);
+        accounts.add_program(target_program);
+
+        let call = TargetCall {
+            program: target_program,
+            accounts: vec![TargetAccount { pubkey: Pubkey::new_unique(), is_writable: true }],
+            payload: vec![1, 2, 3],
+        };
+
+        let origin = [5u8; 32];
+        let root = Content::new(
+            origin,
+            owner.to_bytes(),
+            PROGRAM_ID.to_bytes(),
+            Box::new(TransferData::new_native_transfer(1000).with_relayer_fee(None).with_bitmap_index(None, &bridge_admin_key)),
+        ).with_call(Some(call.clone())).hash().to_bytes();
+        let signed_root_key = accounts.add_signed_root(&bridge_admin_key, root);
+
+        let (withdraw_key, _) = Pubkey::find_program_address(&[&origin], &PROGRAM_ID);
+        let withdraw = withdraw_native(PROGRAM_ID, bridge_admin_key, owner, fee_payer, withdraw_key, SEEDS, origin, 1000,
+                                       RootProof::SignedRoot(signed_root_key), vec![], None, None, None, None, false);
+        let mut instruction = withdraw_and_call(withdraw, target_program, call.payload, vec![AccountMeta::new(call.accounts[0].pubkey, false)]);
+        instruction.accounts.push(AccountMeta::new_readonly(signed_root_key, false));
+
+        (accounts, instruction, owner)
+    }
+
+    #[test]
+    fn init_admin_funds_sol_vault() {
+        let mut accounts = TestAccounts::new();
+        let fee_payer = Pubkey::new_unique();
+        accounts.add_wallet(fee_payer, 1_000_000_000);
+
+        let bridge_admin_key = Pubkey::create_program_address(&[&SEEDS], &PROGRAM_ID).unwrap();
+        let signers = SignerScheme::Ed25519(lib::ed25519::Ed25519SignerSet { public_keys: vec![Pubkey::new_unique()], threshold: 1 });
+        let instruction = initialize_admin(PROGRAM_ID, bridge_admin_key, fee_payer, Pubkey::new_unique(), signers, SEEDS);
+        assert_eq!(accounts.process(&instruction), Ok(()));
+
+        let (sol_vault_key, _) = get_sol_vault_address(&PROGRAM_ID, &bridge_admin_key);
+        assert_eq!(accounts.lamports(&sol_vault_key), Rent::default().minimum_balance(0));
+        assert!(verify_sol_vault_migrated(&PROGRAM_ID, &bridge_admin_key, accounts.get(&sol_vault_key)).is_ok());
+    }
+
+    #[test]
+    fn native_transfers_before_sol_vault_migration() {
+        let mut accounts = TestAccounts::new();
+        let bridge_admin_key = accounts.add_bridge_admin(Pubkey::new_unique());
+        let owner = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        accounts.add_wallet(owner, 1_000_000_000);
+        accounts.add_wallet(fee_payer, 1_000_000_000);
+
+        // Legacy bridge: SOL is still held by the BridgeAdmin account
+        **accounts.get(&bridge_admin_key).try_borrow_mut_lamports().unwrap() += 1_000_000_000;
+
+        let deposit = deposit_native(PROGRAM_ID, bridge_admin_key, owner, SEEDS, "Ethereum".to_string(), 1000,
+                                     "0x0000000000000000000000000000000000000001".to_string(), None, None, false, None);
+        assert_eq!(accounts.process(&deposit), Err(LibError::NotMigrated.into()));
+
+        let withdraw = withdraw_native(PROGRAM_ID, bridge_admin_key, owner, fee_payer, Pubkey::new_unique(), SEEDS, [5u8; 32], 1000,
+                                       RootProof::SignedRoot(Pubkey::new_unique()), vec![], None, None, None, None, false);
+        assert_eq!(accounts.process(&withdraw), Err(LibError::NotMigrated.into()));
+
+        // A vault below its rent-exempt reserve is not migrated either
+        let (sol_vault_key, _) = get_sol_vault_address(&PROGRAM_ID, &bridge_admin_key);
+        accounts.add_wallet(sol_vault_key, Rent::default().minimum_balance(0) - 1);
+        assert_eq!(accounts.process(&withdraw), Err(LibError::NotMigrated.into()));
+        assert_eq!(accounts.lamports(&owner), 1_000_000_000);
+    }
+
+    #[test]
+    fn withdraw_and_call_passes_signed_accounts() {
+        let (mut accounts, instruction, owner) = withdraw_and_call_fixture();
+        assert_eq!(accounts.process(&instruction), Ok(()));
+        assert_eq!(accounts.lamports(&owner), OWNER_BALANCE + 1000);
+
+        let BridgeInstruction::WithdrawAndCall(args) = BridgeInstruction::try_from_slice(&instruction.data).unwrap() else {
+            panic!("not a withdraw and call instruction");
+        };
+        let (call_authority_key, _) = get_call_authority_address(&PROGRAM_ID, &instruction.accounts[0].pubkey, &args.call.program);
+
+        // Neither the fee payer nor the SignedRoot account is passed
+        assert_eq!(invoked().last(), Some(&Instruction {
+            program_id: args.call.program,
+            accounts: vec![
+                AccountMeta::new_readonly(call_authority_key, true),
+                AccountMeta::new(args.call.accounts[0].pubkey, false),
+            ],
+            data: args.call.payload,
+        }));
+    }
+
+    #[test]
+    fn withdraw_and_call_rejects_unsigned_accounts() {
+        // The fee payer instead of the signed account
+        let (mut accounts, mut instruction, _) = withdraw_and_call_fixture();
+        let fee_payer = instruction.accounts[2].pubkey;
+        let call_account = instruction.accounts.len() - 2;
+        instruction.accounts[call_account] = AccountMeta::new(fee_payer, true);
+        assert_eq!(accounts.process(&instruction), Err(LibError::WrongCallAccounts.into()));
+
+        // Call authority of another target program
+        let (mut accounts, mut instruction, _) = withdraw_and_call_fixture();
+        let (call_authority_key, _) = get_call_authority_address(&PROGRAM_ID, &instruction.accounts[0].pubkey, &Pubkey::new_unique());
+        let call_authority = instruction.accounts.len() - 3;
+        instruction.accounts[call_authority] = AccountMeta::new_readonly(call_authority_key, false);
+        assert_eq!(accounts.process(&instruction), Err(LibError::WrongSeeds.into()));
+    }
+
+    #[test]
+    fn withdraw_batch_with_relayer_fees() {
+        let mut accounts = TestAccounts::new();
+        let bridge_admin_key = accounts.add_bridge_admin(Pubkey::new_unique());
+        let sol_vault_key = accounts.add_sol_vault(&bridge_admin_key, 1_000_000_000);
+        let owner = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        accounts.add_wallet(owner, OWNER_BALANCE);
+        accounts.add_wallet(fee_payer, 1_000_000_000);
+
+        // Native token held by the bridge
+        let mint = Pubkey::new_unique();
+        accounts.add_mint(mint, None, 6000, 6);
+        accounts.add_nft_metadata(&mint, &bridge_admin_key, "Token", "TKN", "https://example.com");
+        let bridge_associated = accounts.add_token_account(&bridge_admin_key, &mint, 6000);
+        let owner_associated = accounts.add_token_account(&owner, &mint, 0);
+        let fee_payer_associated = accounts.add_token_account(&fee_payer, &mint, 0);
+
+        let (ft_origin, native_origin) = ([1u8; 32], [2u8; 32]);
+        let ft_hash = Content::new(
+            ft_origin,
+            owner.to_bytes(),
+            PROGRAM_ID.to_bytes(),
+            Box::new(TransferData::new_ft_transfer(mint.to_bytes(), 3000, "Token".to_string(), "TKN".to_string(), "https://example.com".to_string(), 6)
+                .with_relayer_fee(Some(200)).with_bitmap_index(None, &bridge_admin_key)),
+        ).hash();
+        let native_hash = Content::new(
+            native_origin,
+            owner.to_bytes(),
+            PROGRAM_ID.to_bytes(),
+            Box::new(TransferData::new_native_transfer(1000).with_relayer_fee(Some(100)).with_bitmap_index(None, &bridge_admin_key)),
+        ).hash();
+
+        // Both transfers are the leaves of the same signed root
+        let root = get_merkle_root(ft_hash, &vec![native_hash.to_bytes()]).unwrap();
+        assert_eq!(get_merkle_root(native_hash, &vec![ft_hash.to_bytes()]), Ok(root));
+        let signed_root_key = accounts.add_signed_root(&bridge_admin_key, root);
+        let proof = RootProof::SignedRoot(signed_root_key);
+
+        let (ft_withdraw_key, _) = Pubkey::find_program_address(&[&ft_origin], &PROGRAM_ID);
+        let (native_withdraw_key, _) = Pubkey::find_program_address(&[&native_origin], &PROGRAM_ID);
+        let mut batch = withdraw_batch(vec![
+            withdraw_ft(PROGRAM_ID, bridge_admin_key, mint, spl_token::id(), owner, fee_payer, ft_withdraw_key, SEEDS, ft_origin, 3000,
+                        proof.clone(), vec![native_hash.to_bytes()], None, None, Some(200), None),
+            withdraw_native(PROGRAM_ID, bridge_admin_key, owner, fee_payer, native_withdraw_key, SEEDS, native_origin, 1000,
+                            proof, vec![ft_hash.to_bytes()], None, None, Some(100), None, false),
+        ]);
+        batch.accounts.push(AccountMeta::new_readonly(signed_root_key, false));
+        let vault_balance = accounts.lamports(&sol_vault_key);
+
+        assert_eq!(accounts.process(&batch), Ok(()));
+        assert_eq!(accounts.token_amount(&bridge_associated), 3000);
+        assert_eq!(accounts.token_amount(&owner_associated), 2800);
+        assert_eq!(accounts.token_amount(&fee_payer_associated), 200);
+        assert_eq!(accounts.lamports(&owner), OWNER_BALANCE + 900);
+        assert_eq!(accounts.lamports(&sol_vault_key), vault_balance - 1000);
+        assert!(!accounts.get(&ft_withdraw_key).data_is_empty());
+        assert!(!accounts.get(&native_withdraw_key).data_is_empty());
+
+        // The same transfers can not be withdrawn twice
+        assert_eq!(accounts.process(&batch), Err(ACCOUNT_ALREADY_IN_USE));
+    }
+
+    #[test]
+    fn wrapped_nft_withdraw_after_deposit() {
+        let mut accounts = TestAccounts::new();
+        let bridge_admin_key = accounts.add_bridge_admin(Pubkey::new_unique());
+        let owner = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        accounts.add_wallet(owner, OWNER_BALANCE);
+        accounts.add_wallet(fee_payer, 1_000_000_000);
+
+        // Wrapped NFT after the first withdrawal: the master edition holds the mint authority
+        let token_seed = [9u8; 32];
+        let (mint, _) = Pubkey::find_program_address(&[&token_seed], &PROGRAM_ID);
+        accounts.add_mint(mint, Some(MasterEdition::find_pda(&mint).0), 1, 0);
+        accounts.add_nft_metadata(&mint, &bridge_admin_key, "Wrapped", "WRP", "https://example.com");
+        let owner_associated = accounts.add_token_account(&owner, &mint, 1);
+        let bridge_associated = accounts.add_token_account(&bridge_admin_key, &mint, 0);
+
+        let deposit = deposit_nft(PROGRAM_ID, bridge_admin_key, mint, spl_token::id(), owner, SEEDS, "Ethereum".to_string(),
+                                  "0x0000000000000000000000000000000000000001".to_string(), Some(token_seed), None, None, false, None, None);
+        accounts.set_commission_charged(&bridge_admin_key, &owner, lib::TokenType::NFT, 1, &deposit);
+        assert_eq!(accounts.process(&deposit), Ok(()));
+        assert_eq!(accounts.token_amount(&owner_associated), 0);
+        assert_eq!(accounts.token_amount(&bridge_associated), 1);
+
+        // The same token seed is withdrawn again
+        let origin = [6u8; 32];
+        let root = Content::new(
+            origin,
+            owner.to_bytes(),
+            PROGRAM_ID.to_bytes(),
+            Box::new(TransferData::new_nft_transfer(mint.to_bytes(), None, "Wrapped".to_string(), "WRP".to_string(), "https://example.com".to_string())
+                .with_royalties(None).with_bitmap_index(None, &bridge_admin_key)),
+        ).hash().to_bytes();
+        let signed_root_key = accounts.add_signed_root(&bridge_admin_key, root);
+        let signed_meta = SignedMetadata {
+            name: "Wrapped".to_string(),
+            symbol: "WRP".to_string(),
+            uri: "https://example.com".to_string(),
+            decimals: 0,
+            collection: None,
+            royalties: None,
+        };
+
+        let (withdraw_key, _) = Pubkey::find_program_address(&[&origin], &PROGRAM_ID);
+        let mut withdraw = withdraw_nft(PROGRAM_ID, bridge_admin_key, mint, spl_token::id(), Metadata::find_pda(&mint).0, owner, fee_payer, withdraw_key,
+                                        SEEDS, origin, 1, RootProof::SignedRoot(signed_root_key), vec![], Some(token_seed), Some(signed_meta), None, false, None);
+        withdraw.accounts.push(AccountMeta::new_readonly(signed_root_key, false));
+        assert_eq!(accounts.process(&withdraw), Ok(()));
+        assert_eq!(accounts.token_amount(&owner_associated), 1);
+        assert_eq!(accounts.token_amount(&bridge_associated), 0);
+    }
+
+    fn withdraw_wrapped_cnft(accounts: &mut TestAccounts, bridge_admin_key: Pubkey, owner: Pubkey, fee_payer: Pubkey, merkle_tree: Pubkey, token_seed: [u8; 32], origin: [u8; 32]) -> ProgramResult {
+        let (asset_id, _) = Pubkey::find_program_address(&[&token_seed], &PROGRAM_ID);
+        let root = Content::new(
+            origin,
+            owner.to_bytes(),
+            PROGRAM_ID.to_bytes(),
+            Box::new(TransferData::new_nft_transfer(asset_id.to_bytes(), None, "Wrapped".to_string(), "WRP".to_string(), "https://example.com".to_string())
+                .with_royalties(None).with_compressed().with_bitmap_index(None, &bridge_admin_key)),
+        ).hash().to_bytes();
+        let signed_root_key = accounts.add_signed_root(&bridge_admin_key, root);
+        let signed_meta = SignedMetadata {
+            name: "Wrapped".to_string(),
+            symbol: "WRP".to_string(),
+            uri: "https://example.com".to_string(),
+            decimals: 0,
+            collection: None,
+            royalties: None,
+        };
+
+        let (withdraw_key, _) = Pubkey::find_program_address(&[&origin], &PROGRAM_ID);
+        let mut withdraw = withdraw_cnft(PROGRAM_ID, bridge_admin_key, owner, fee_payer, withdraw_key, merkle_tree, SEEDS, origin,
+                                         RootProof::SignedRoot(signed_root_key), vec![], None, vec![], Some(token_seed), Some(signed_meta), None);
+        withdraw.accounts.push(AccountMeta::new_readonly(signed_root_key, false));
+        accounts.process(&withdraw)
+    }
+
+    #[test]
+    fn wrapped_cnft_withdraw_after_deposit() {
+        let mut accounts = TestAccounts::new();
+        let bridge_admin_key = accounts.add_bridge_admin(Pubkey::new_unique());
+        let owner = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        accounts.add_wallet(owner, OWNER_BALANCE);
+        accounts.add_wallet(fee_payer, 1_000_000_000);
+
+        let merkle_tree = accounts.add_bridge_tree(&bridge_admin_key, 5);
+        let token_seed = [9u8; 32];
+        let (wrapped_asset_key, _) = get_wrapped_asset_address(&PROGRAM_ID, &bridge_admin_key, token_seed);
+        let wrapped_asset = |accounts: &TestAccounts| -> WrappedAsset {
+            BorshDeserialize::deserialize(&mut accounts.get(&wrapped_asset_key).data.borrow().as_ref()).unwrap()
+        };
+
+        // The minted leaf gets the next nonce of the tree
+        let leaf_asset_id = mpl_bubblegum::utils::get_asset_id(&merkle_tree, 5);
+        assert_eq!(withdraw_wrapped_cnft(&mut accounts, bridge_admin_key, owner, fee_payer, merkle_tree, token_seed, [6u8; 32]), Ok(()));
+        assert!(invoked().iter().any(|instruction| instruction.program_id == mpl_bubblegum::ID));
+        assert_eq!(wrapped_asset(&accounts).asset_id, Some(leaf_asset_id));
+
+        // The second leaf is not minted until the first one is deposited
+        assert_eq!(withdraw_wrapped_cnft(&mut accounts, bridge_admin_key, owner, fee_payer, merkle_tree, token_seed, [7u8; 32]), Err(LibError::AlreadyMinted.into()));
+
+        let mut leaf = CompressedLeaf {
+            root: [1u8; 32],
+            nonce: 4,
+            index: 4,
+            metadata: MetadataArgs {
+                name: "Wrapped".to_string(),
+                symbol: "WRP".to_string(),
+                uri: "https://example.com".to_string(),
+                seller_fee_basis_points: 0,
+                primary_sale_happened: false,
+                is_mutable: true,
+                edition_nonce: None,
+                token_standard: Some(LeafTokenStandard::NonFungible),
+                collection: None,
+                uses: None,
+                token_program_version: TokenProgramVersion::Original,
+                creators: Vec::new(),
+            },
+        };
+        let deposit = |leaf: &CompressedLeaf| deposit_cnft(PROGRAM_ID, bridge_admin_key, owner, owner, merkle_tree, SEEDS, "Ethereum".to_string(),
+                                                           "0x0000000000000000000000000000000000000001".to_string(), leaf.clone(), vec![], Some(token_seed), None, None, None);
+
+        // Other leaf of the tree can not be deposited as the wrapped asset
+        accounts.set_commission_charged(&bridge_admin_key, &owner, lib::TokenType::CNFT, 1, &deposit(&leaf));
+        assert_eq!(accounts.process(&deposit(&leaf)), Err(LibError::WrongTokenSeed.into()));
+
+        leaf.nonce = 5;
+        leaf.index = 5;
+        accounts.set_commission_charged(&bridge_admin_key, &owner, lib::TokenType::CNFT, 1, &deposit(&leaf));
+        assert_eq!(accounts.process(&deposit(&leaf)), Ok(()));
+        assert_eq!(invoked().last(), Some(&BurnLeaf {
+            tree_config: TreeConfig::find_pda(&merkle_tree).0,
+            leaf_owner: (owner, true),
+            leaf_delegate: (owner, false),
+            merkle_tree,
+            log_wrapper: mpl_bubblegum::programs::SPL_NOOP_ID,
+            compression_program: mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID,
+            system_program: solana_program::system_program::id(),
+        }.instruction(BurnLeafInstructionArgs {
+            root: leaf.root,
+            data_hash: hash_metadata(&leaf.metadata).unwrap(),
+            creator_hash: hash_creators(&leaf.metadata.creators),
+            nonce: 5,
+            index: 5,
+        })));
+        assert_eq!(wrapped_asset(&accounts).asset_id, None);
+
+        assert_eq!(withdraw_wrapped_cnft(&mut accounts, bridge_admin_key, owner, fee_payer, merkle_tree, token_seed, [7u8; 32]), Ok(()));
+        assert_eq!(wrapped_asset(&accounts).asset_id, Some(leaf_asset_id));
+    }
+}
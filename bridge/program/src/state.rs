@@ -2,15 +2,87 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
 use solana_program::secp256k1_recover::SECP256K1_PUBLIC_KEY_LENGTH;
 use lib::TokenType;
-use lib::instructions::bridge::{MAX_NETWORKS_SIZE, MAX_ADDRESS_SIZE};
+use lib::signers::{SignerScheme, SIGNER_SCHEME_SIZE};
+use lib::instructions::bridge::{MAX_NETWORKS_SIZE, MAX_ADDRESS_SIZE, MAX_PAYLOAD_SIZE, MAX_POLICY_TOKENS_COUNT, WITHDRAW_BITMAP_BITS, PauseState, TokenPolicyMode, WithdrawMode};
+use lib::networks::{Network, MAX_NETWORKS_COUNT, NETWORK_SIZE};
 use std::mem::size_of;
+use std::cmp::min;
+use solana_program::entrypoint::ProgramResult;
+use lib::error::LibError;
 
-pub const BRIDGE_ADMIN_SIZE: usize = SECP256K1_PUBLIC_KEY_LENGTH + (32 as usize) + 1;
-pub const WITHDRAW_SIZE: usize = size_of::<TokenType>() + (32 as usize) + (8 as usize) + MAX_NETWORKS_SIZE + MAX_ADDRESS_SIZE + 1;
+pub const LEGACY_BRIDGE_ADMIN_SIZE: usize = SECP256K1_PUBLIC_KEY_LENGTH + 32 + 1;
+pub const BRIDGE_ADMIN_SIZE: usize = SIGNER_SCHEME_SIZE + 32 + 8 + 8 + size_of::<PauseState>() + 8 + size_of::<WithdrawMode>() + 1;
+pub const DEPOSIT_SIZE: usize = 8 + size_of::<TokenType>() + 1 + 32 + 8 + 32 +
+    4 + MAX_NETWORKS_SIZE + 4 + MAX_ADDRESS_SIZE + 1 + 32 + 8 + 1;
+pub const MESSAGE_SIZE: usize = 8 + 32 + 4 + MAX_NETWORKS_SIZE + 4 + MAX_ADDRESS_SIZE +
+    4 + MAX_PAYLOAD_SIZE + 8 + 1;
+pub const MESSAGE_RECEIPT_SIZE: usize = 32 + 4 + MAX_ADDRESS_SIZE + 32 + 4 + MAX_PAYLOAD_SIZE + 1;
+pub const NETWORK_REGISTRY_SIZE: usize = 4 + MAX_NETWORKS_COUNT * NETWORK_SIZE + 8 + 1;
+pub const RATE_LIMIT_SIZE: usize = 1 + 32 + 8 * 5 + 1;
+pub const DEPOSIT_LIMIT_SIZE: usize = 1 + 32 + 8 * 3 + 1;
+pub const TOKEN_POLICY_SIZE: usize = 1 + 4 + MAX_POLICY_TOKENS_COUNT * 32 + 8 + 1;
+pub const SIGNED_ROOT_SIZE: usize = 32 + 32 + 32 + 8 + 1 + 1;
+pub const WITHDRAW_BITMAP_SIZE: usize = 8 + 4 + (WITHDRAW_BITMAP_BITS / 8) as usize + 1 + 1;
+pub const LEGACY_WITHDRAW_SIZE: usize = size_of::<TokenType>() + 32 + 8 + MAX_NETWORKS_SIZE + MAX_ADDRESS_SIZE + 1;
+pub const WITHDRAW_SIZE: usize = LEGACY_WITHDRAW_SIZE + 32;
+pub const METADATA_NONCE_SIZE: usize = 32 + 8 + 1;
+pub const WRAPPED_ASSET_SIZE: usize = 1 + 32 + 1;
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub enum OperationType {
+    AddNetwork,
+    RemoveNetwork,
+    SetPause,
+    SetRateLimit,
+    SetDepositLimit,
+    SetTokenPolicy,
+    SetWithdrawMode,
+    CloseWithdraw,
+    UpdateWrappedMetadata,
+    InitNetworkRegistry,
+    MigrateAdmin,
+    MigrateSolVault,
+}
+
+impl From<OperationType> for u8 {
+    fn from(operation: OperationType) -> u8 {
+        match operation {
+            OperationType::AddNetwork => 0,
+            OperationType::RemoveNetwork => 1,
+            OperationType::SetPause => 2,
+            OperationType::SetRateLimit => 3,
+            OperationType::SetDepositLimit => 4,
+            OperationType::SetTokenPolicy => 5,
+            OperationType::SetWithdrawMode => 6,
+            OperationType::CloseWithdraw => 7,
+            OperationType::UpdateWrappedMetadata => 8,
+            OperationType::InitNetworkRegistry => 9,
+            OperationType::MigrateAdmin => 10,
+            OperationType::MigrateSolVault => 11,
+        }
+    }
+}
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct BridgeAdmin {
+    pub signers: SignerScheme,
+    pub commission_program: Pubkey,
+    pub deposit_nonce: u64,
+    pub message_nonce: u64,
+    pub pause: PauseState,
+    // Nonce of the signed operations with the bridge admin state: SetPause, SetWithdrawMode, CloseWithdraw,
+    // InitializeNetworkRegistry and MigrateSolVault
+    pub admin_nonce: u64,
+    pub withdraw_mode: WithdrawMode,
+    pub is_initialized: bool,
+}
+
+// BridgeAdmin layout before the signer sets, see `MigrateAdmin` instruction
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct LegacyBridgeAdmin {
     pub public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
     pub commission_program: Pubkey,
     pub is_initialized: bool,
@@ -26,4 +98,290 @@ pub struct Withdraw {
     pub origin: [u8; 32],
     pub receiver_address: Pubkey,
     pub is_initialized: bool,
-}
\ No newline at end of file
+    // Fee payer that paid the rent. Empty in the legacy accounts, their rent was paid by the receiver.
+    pub payer: Pubkey,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Deposit {
+    // Value of BridgeAdmin deposit nonce for that deposit
+    pub nonce: u64,
+    pub token_type: lib::TokenType,
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub sender: Pubkey,
+    pub network_to: String,
+    pub receiver_address: String,
+    pub bundle_salt: Option<[u8; 32]>,
+    pub slot: u64,
+    pub is_initialized: bool,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Message {
+    // Value of BridgeAdmin message nonce for that message
+    pub nonce: u64,
+    pub sender: Pubkey,
+    pub network_to: String,
+    pub target_address: String,
+    pub payload: Vec<u8>,
+    pub slot: u64,
+    pub is_initialized: bool,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct MessageReceipt {
+    // Hash of message tx info. See spec in core for more information.
+    pub origin: [u8; 32],
+    pub sender: String,
+    pub receiver_address: Pubkey,
+    pub payload: Vec<u8>,
+    pub is_initialized: bool,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct NetworkRegistry {
+    pub networks: Vec<Network>,
+    pub nonce: u64,
+    pub is_initialized: bool,
+}
+
+impl NetworkRegistry {
+    pub fn find(&self, name: &str) -> Option<&Network> {
+        self.networks.iter().find(|n| n.name == name)
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct RateLimit {
+    // Empty if is native
+    pub mint: Option<Pubkey>,
+    pub capacity: u64,
+    pub refill_per_second: u64,
+    // Amount available for withdrawal at the moment of last update
+    pub available: u64,
+    // Unix timestamp of last update
+    pub last_update: i64,
+    pub nonce: u64,
+    pub is_initialized: bool,
+}
+
+impl RateLimit {
+    fn refill(&mut self, now: i64) {
+        let elapsed = if now > self.last_update { (now - self.last_update) as u64 } else { 0 };
+        self.available = min(self.capacity, self.available.saturating_add(elapsed.saturating_mul(self.refill_per_second)));
+        self.last_update = now;
+    }
+
+    pub fn consume(&mut self, amount: u64, now: i64) -> ProgramResult {
+        self.refill(now);
+        if amount > self.available {
+            return Err(LibError::RateLimitExceeded.into());
+        }
+
+        self.available -= amount;
+        Ok(())
+    }
+
+    pub fn update(&mut self, capacity: u64, refill_per_second: u64, now: i64) {
+        self.refill(now);
+        self.capacity = capacity;
+        self.refill_per_second = refill_per_second;
+        self.available = min(self.available, capacity);
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct DepositLimit {
+    // Empty if is native
+    pub mint: Option<Pubkey>,
+    pub min_amount: u64,
+    pub max_amount: u64,
+    pub nonce: u64,
+    pub is_initialized: bool,
+}
+
+impl DepositLimit {
+    pub fn check(&self, amount: u64) -> ProgramResult {
+        if amount < self.min_amount || amount > self.max_amount {
+            return Err(LibError::WrongDepositAmount.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct TokenPolicy {
+    pub mode: TokenPolicyMode,
+    pub mints: Vec<Pubkey>,
+    pub nonce: u64,
+    pub is_initialized: bool,
+}
+
+impl TokenPolicy {
+    pub fn check(&self, mint: &Pubkey) -> ProgramResult {
+        let listed = self.mints.contains(mint);
+        let allowed = match self.mode {
+            TokenPolicyMode::Disabled => true,
+            TokenPolicyMode::Allowlist => listed,
+            TokenPolicyMode::Denylist => !listed,
+        };
+
+        if !allowed {
+            return Err(LibError::TokenNotAllowed.into());
+        }
+
+        Ok(())
+    }
+}
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SignedRoot {
+    pub bridge_admin: Pubkey,
+    pub root: [u8; 32],
+    // Hash of the signer scheme that signed the root, see `SignerScheme::hash`
+    pub signers_hash: [u8; 32],
+    pub slot: u64,
+    pub bump: u8,
+    pub is_initialized: bool,
+}
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WithdrawBitmap {
+    // Index of the shard, covers withdrawal indexes [shard * WITHDRAW_BITMAP_BITS, (shard + 1) * WITHDRAW_BITMAP_BITS)
+    pub shard: u64,
+    pub bits: Vec<u8>,
+    pub bump: u8,
+    pub is_initialized: bool,
+}
+
+impl WithdrawBitmap {
+    // Sets the bit of the withdrawal index, fails if it was already set
+    pub fn set(&mut self, bitmap_index: u64) -> ProgramResult {
+        let bit = bitmap_index % WITHDRAW_BITMAP_BITS;
+        let byte = (bit / 8) as usize;
+        let mask = 1u8 << (bit % 8);
+
+        if self.bits[byte] & mask != 0 {
+            return Err(LibError::AlreadyInUse.into());
+        }
+
+        self.bits[byte] |= mask;
+        Ok(())
+    }
+}
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct MetadataNonce {
+    pub mint: Pubkey,
+    // Nonce of the next signed metadata update of the mint
+    pub nonce: u64,
+    pub is_initialized: bool,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WrappedAsset {
+    // Bubblegum asset id of the leaf minted for the token seed, empty after its deposit
+    pub asset_id: Option<Pubkey>,
+    pub is_initialized: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib::error::LibError;
+
+    const RATE_LIMIT: RateLimit = RateLimit {
+        mint: None,
+        capacity: 1000,
+        refill_per_second: 10,
+        available: 1000,
+        last_update: 100,
+        nonce: 0,
+        is_initialized: true,
+    };
+
+    #[test]
+    fn rate_limit_consume() {
+        let mut limit = RATE_LIMIT;
+
+        assert!(limit.consume(600, 100).is_ok());
+        assert_eq!(limit.available, 400);
+        assert_eq!(limit.consume(401, 100), Err(LibError::RateLimitExceeded.into()));
+        assert_eq!(limit.available, 400);
+        assert!(limit.consume(400, 100).is_ok());
+        assert_eq!(limit.available, 0);
+    }
+
+    #[test]
+    fn rate_limit_refill() {
+        let mut limit = RateLimit { available: 0, ..RATE_LIMIT };
+
+        // 10 seconds refill 100
+        assert!(limit.consume(100, 110).is_ok());
+        assert_eq!(limit.available, 0);
+        assert_eq!(limit.last_update, 110);
+
+        // Refill stops at the capacity
+        assert!(limit.consume(0, 1_000_000).is_ok());
+        assert_eq!(limit.available, 1000);
+
+        // Clock going back does not refill or rewind the window
+        let mut limit = RateLimit { available: 0, ..RATE_LIMIT };
+        assert_eq!(limit.consume(1, 50), Err(LibError::RateLimitExceeded.into()));
+        assert_eq!(limit.available, 0);
+
+        // Large elapsed time and rate saturate instead of overflowing
+        let mut limit = RateLimit { capacity: u64::MAX, refill_per_second: u64::MAX, available: 1, last_update: 0, ..RATE_LIMIT };
+        assert!(limit.consume(u64::MAX, i64::MAX).is_ok());
+        assert_eq!(limit.available, 0);
+    }
+
+    #[test]
+    fn rate_limit_update() {
+        let mut limit = RateLimit { available: 500, ..RATE_LIMIT };
+
+        // Refills before the new capacity is applied
+        limit.update(2000, 20, 110);
+        assert_eq!((limit.capacity, limit.refill_per_second, limit.available, limit.last_update), (2000, 20, 600, 110));
+
+        // Lower capacity caps the available amount
+        limit.update(100, 1, 110);
+        assert_eq!(limit.available, 100);
+    }
+
+    #[test]
+    fn withdraw_bitmap_set() {
+        let mut bitmap = WithdrawBitmap { shard: 0, bits: vec![0; (WITHDRAW_BITMAP_BITS / 8) as usize], bump: 0, is_initialized: true };
+
+        assert!(bitmap.set(0).is_ok());
+        assert!(bitmap.set(9).is_ok());
+        assert!(bitmap.set(WITHDRAW_BITMAP_BITS - 1).is_ok());
+        assert_eq!(bitmap.bits[0], 0b0000_0001);
+        assert_eq!(bitmap.bits[1], 0b0000_0010);
+        assert_eq!(bitmap.bits[bitmap.bits.len() - 1], 0b1000_0000);
+
+        assert_eq!(bitmap.set(0), Err(LibError::AlreadyInUse.into()));
+        assert_eq!(bitmap.set(9), Err(LibError::AlreadyInUse.into()));
+        assert!(bitmap.set(1).is_ok());
+    }
+
+    #[test]
+    fn withdraw_bitmap_set_in_shard() {
+        let mut bitmap = WithdrawBitmap { shard: 3, bits: vec![0; (WITHDRAW_BITMAP_BITS / 8) as usize], bump: 0, is_initialized: true };
+        let first = 3 * WITHDRAW_BITMAP_BITS;
+
+        assert!(bitmap.set(first + 10).is_ok());
+        assert_eq!(bitmap.bits[1], 0b0000_0100);
+        assert_eq!(bitmap.set(first + 10), Err(LibError::AlreadyInUse.into()));
+    }
+}
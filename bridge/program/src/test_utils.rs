@@ -0,0 +1,421 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use lib::ed25519::Ed25519SignerSet;
+use lib::instructions::bridge::{get_signed_root_address, get_sol_vault_address, PauseState, WithdrawMode};
+use lib::instructions::commission::charge_commission_native;
+use lib::signers::SignerScheme;
+use lib::{CommissionToken, TokenType};
+use mpl_bubblegum::accounts::TreeConfig;
+use mpl_bubblegum::types::DecompressibleState;
+use mpl_token_metadata::accounts::{MasterEdition, Metadata};
+use mpl_token_metadata::types::{Key, TokenStandard};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    program_option::COption,
+    program_pack::Pack,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{SystemError, SystemInstruction},
+    system_program,
+    sysvar,
+    sysvar::instructions::{construct_instructions_data, store_current_index, BorrowedAccountMeta, BorrowedInstruction},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account, AccountState, Mint};
+
+use crate::merkle::TransferData;
+use crate::state::{BridgeAdmin, SignedRoot, BRIDGE_ADMIN_SIZE, SIGNED_ROOT_SIZE};
+
+pub const MINT: [u8; 32] = [1u8; 32];
+pub const PROGRAM_ID: Pubkey = Pubkey::new_from_array([7u8; 32]);
+pub const SEEDS: [u8; 32] = [3u8; 32];
+// Error of the system program for the accounts that are already created
+pub const ACCOUNT_ALREADY_IN_USE: ProgramError = ProgramError::Custom(SystemError::AccountAlreadyInUse as u32);
+
+pub fn ft_transfer() -> TransferData {
+    TransferData::new_ft_transfer(MINT, 1000, "Token".to_string(), "TKN".to_string(), "https://example.com".to_string(), 9)
+}
+
+thread_local! {
+    // Instructions invoked by the program under test
+    static INVOKED: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    // Data buffers of the accounts allocated by the invoked system program
+    static ALLOCATED: RefCell<Vec<(Pubkey, *mut [u8])>> = const { RefCell::new(Vec::new()) };
+}
+
+// Runs the system and SPL Token instructions invoked by the program, other programs are only recorded
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(&self, instruction: &Instruction, account_infos: &[AccountInfo], signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+        let signers = signers_seeds.iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &PROGRAM_ID))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut accounts = Vec::new();
+        for meta in &instruction.accounts {
+            let mut info = account_infos.iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?
+                .clone();
+
+            if meta.is_signer && !info.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            if meta.is_writable && !info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            info.is_signer = meta.is_signer;
+            info.is_writable = meta.is_writable;
+            accounts.push(info);
+        }
+
+        INVOKED.with(|invoked| invoked.borrow_mut().push(instruction.clone()));
+
+        if instruction.program_id == system_program::id() {
+            process_system_instruction(&accounts, &instruction.data)
+        } else if instruction.program_id == spl_token::id() {
+            spl_token::processor::Processor::process(&instruction.program_id, &accounts, &instruction.data)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        solana_program::entrypoint::SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        solana_program::entrypoint::SUCCESS
+    }
+}
+
+fn process_system_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    match limited_deserialize(data, 1024).map_err(|_| ProgramError::InvalidInstructionData)? {
+        SystemInstruction::CreateAccount { lamports, space, owner } => {
+            if accounts[1].lamports() > 0 {
+                return Err(ACCOUNT_ALREADY_IN_USE);
+            }
+
+            transfer(&accounts[0], &accounts[1], lamports)?;
+            allocate(&accounts[1], space)?;
+            accounts[1].assign(&owner);
+        }
+        SystemInstruction::Transfer { lamports } => transfer(&accounts[0], &accounts[1], lamports)?,
+        SystemInstruction::Allocate { space } => allocate(&accounts[0], space)?,
+        SystemInstruction::Assign { owner } => {
+            if *accounts[0].owner != system_program::id() {
+                return Err(ACCOUNT_ALREADY_IN_USE);
+            }
+
+            accounts[0].assign(&owner)
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+
+    Ok(())
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if *from.owner != system_program::id() || !from.data_is_empty() || from.lamports() < lamports {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    **from.try_borrow_mut_lamports()? -= lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+
+    // The runtime rejects new accounts below the rent-exempt reserve
+    if to.lamports() < Rent::default().minimum_balance(to.data_len()) {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    Ok(())
+}
+
+fn allocate(info: &AccountInfo, space: u64) -> ProgramResult {
+    if *info.owner != system_program::id() || !info.data_is_empty() {
+        return Err(ACCOUNT_ALREADY_IN_USE);
+    }
+
+    let buffer = Box::into_raw(vec![0u8; space as usize].into_boxed_slice());
+    ALLOCATED.with(|allocated| allocated.borrow_mut().push((*info.key, buffer)));
+    *info.data.borrow_mut() = unsafe { &mut *buffer };
+    Ok(())
+}
+
+pub fn invoked() -> Vec<Instruction> {
+    INVOKED.with(|invoked| invoked.borrow().clone())
+}
+
+// Accounts of the test ledger, leaked to have the static lifetime of the processed instructions.
+// The program serializes the state with `Write for &mut [u8]` that advances the data slice of the account,
+// so the slice is reset to the whole buffer before every instruction and read.
+pub struct TestAccounts {
+    infos: HashMap<Pubkey, AccountInfo<'static>>,
+    buffers: HashMap<Pubkey, *mut [u8]>,
+}
+
+impl TestAccounts {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let rent = Rent::default();
+        let mut rent_data = Vec::new();
+        rent_data.extend(rent.lamports_per_byte_year.to_le_bytes());
+        rent_data.extend(rent.exemption_threshold.to_le_bytes());
+        rent_data.push(rent.burn_percent);
+
+        let mut accounts = TestAccounts { infos: HashMap::new(), buffers: HashMap::new() };
+        accounts.add(sysvar::rent::id(), 1, rent_data, sysvar::id());
+        accounts.set_instructions(&[]);
+        accounts
+    }
+
+    pub fn add(&mut self, key: Pubkey, lamports: u64, data: Vec<u8>, owner: Pubkey) {
+        self.insert(key, lamports, data, owner, false);
+    }
+
+    pub fn add_program(&mut self, key: Pubkey) {
+        self.insert(key, 1, vec![], solana_program::bpf_loader::id(), true);
+    }
+
+    fn insert(&mut self, key: Pubkey, lamports: u64, data: Vec<u8>, owner: Pubkey, executable: bool) {
+        let buffer = Box::into_raw(data.into_boxed_slice());
+        let info = AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            false,
+            Box::leak(Box::new(lamports)),
+            unsafe { &mut *buffer },
+            Box::leak(Box::new(owner)),
+            executable,
+            0,
+        );
+
+        self.infos.insert(key, info);
+        self.buffers.insert(key, buffer);
+    }
+
+    pub fn add_wallet(&mut self, key: Pubkey, lamports: u64) {
+        self.add(key, lamports, vec![], system_program::id());
+    }
+
+    pub fn get(&self, key: &Pubkey) -> &AccountInfo<'static> {
+        let info = &self.infos[key];
+        *info.data.borrow_mut() = unsafe { &mut *self.buffers[key] };
+        info
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.infos.get(key).map_or(0, |info| info.lamports())
+    }
+
+    // Instructions sysvar of the transaction with the processed instruction at the last position
+    pub fn set_instructions(&mut self, instructions: &[Instruction]) {
+        let borrowed = instructions.iter()
+            .map(|instruction| BorrowedInstruction {
+                program_id: &instruction.program_id,
+                accounts: instruction.accounts.iter()
+                    .map(|meta| BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: &instruction.data,
+            })
+            .collect::<Vec<_>>();
+
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, instructions.len().saturating_sub(1) as u16);
+
+        self.add(sysvar::instructions::id(), 1, data, sysvar::id());
+    }
+
+    pub fn process(&mut self, instruction: &Instruction) -> ProgramResult {
+        INVOKED.with(|invoked| invoked.borrow_mut().clear());
+
+        let infos = instruction.accounts.iter()
+            .map(|meta| {
+                if !self.infos.contains_key(&meta.pubkey) {
+                    self.add_wallet(meta.pubkey, 0);
+                }
+
+                let mut info = self.get(&meta.pubkey).clone();
+                info.is_signer = meta.is_signer;
+                info.is_writable = meta.is_writable;
+                info
+            })
+            .collect::<Vec<_>>();
+
+        let result = crate::processor::process_instruction(&PROGRAM_ID, Box::leak(infos.into_boxed_slice()), &instruction.data);
+        ALLOCATED.with(|allocated| self.buffers.extend(allocated.borrow_mut().drain(..)));
+        result
+    }
+
+    // Initialized BridgeAdmin of `SEEDS` with the Ed25519 signer
+    pub fn add_bridge_admin(&mut self, signer: Pubkey) -> Pubkey {
+        let bridge_admin_key = Pubkey::create_program_address(&[&SEEDS], &PROGRAM_ID).unwrap();
+        let bridge_admin = BridgeAdmin {
+            signers: SignerScheme::Ed25519(Ed25519SignerSet { public_keys: vec![signer], threshold: 1 }),
+            commission_program: commission_program(&bridge_admin_key),
+            deposit_nonce: 0,
+            message_nonce: 0,
+            pause: PauseState::default(),
+            admin_nonce: 0,
+            withdraw_mode: WithdrawMode::default(),
+            is_initialized: true,
+        };
+
+        let mut data = bridge_admin.try_to_vec().unwrap();
+        data.resize(BRIDGE_ADMIN_SIZE, 0);
+        self.add(bridge_admin_key, Rent::default().minimum_balance(BRIDGE_ADMIN_SIZE), data, PROGRAM_ID);
+        bridge_admin_key
+    }
+
+    // SOL vault of the migrated bridge with the lamports above its reserve
+    pub fn add_sol_vault(&mut self, bridge_admin_key: &Pubkey, lamports: u64) -> Pubkey {
+        let (sol_vault_key, _) = get_sol_vault_address(&PROGRAM_ID, bridge_admin_key);
+        self.add_wallet(sol_vault_key, Rent::default().minimum_balance(0) + lamports);
+        sol_vault_key
+    }
+
+    // SignedRoot submitted by the current signers of the bridge admin
+    pub fn add_signed_root(&mut self, bridge_admin_key: &Pubkey, root: [u8; 32]) -> Pubkey {
+        let bridge_admin = BridgeAdmin::deserialize(&mut self.get(bridge_admin_key).data.borrow().as_ref()).unwrap();
+        let (signed_root_key, bump) = get_signed_root_address(&PROGRAM_ID, bridge_admin_key, &root);
+        let signed_root = SignedRoot {
+            bridge_admin: *bridge_admin_key,
+            root,
+            signers_hash: bridge_admin.signers.hash().unwrap(),
+            slot: 0,
+            bump,
+            is_initialized: true,
+        };
+
+        let mut data = signed_root.try_to_vec().unwrap();
+        data.resize(SIGNED_ROOT_SIZE, 0);
+        self.add(signed_root_key, Rent::default().minimum_balance(SIGNED_ROOT_SIZE), data, PROGRAM_ID);
+        signed_root_key
+    }
+
+    // Transaction with the commission charged for the deposit instruction
+    pub fn set_commission_charged(&mut self, bridge_admin_key: &Pubkey, owner: &Pubkey, token: TokenType, amount: u64, deposit: &Instruction) {
+        let commission_program = commission_program(bridge_admin_key);
+        let commission_admin = commission_admin(bridge_admin_key, &commission_program).unwrap();
+        let charge = charge_commission_native(commission_program, commission_admin, *bridge_admin_key, *owner, CommissionToken::Native, token, amount);
+        self.set_instructions(&[charge, deposit.clone()]);
+    }
+
+    pub fn add_mint(&mut self, key: Pubkey, mint_authority: Option<Pubkey>, supply: u64, decimals: u8) {
+        let mint = Mint {
+            mint_authority: mint_authority.into(),
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+
+        let mut data = vec![0u8; Mint::LEN];
+        Mint::pack(mint, &mut data).unwrap();
+        self.add(key, Rent::default().minimum_balance(Mint::LEN), data, spl_token::id());
+    }
+
+    // Associated token account of the owner
+    pub fn add_token_account(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let account = Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Account::default()
+        };
+
+        let key = get_associated_token_address(owner, mint);
+        let mut data = vec![0u8; Account::LEN];
+        Account::pack(account, &mut data).unwrap();
+        self.add(key, Rent::default().minimum_balance(Account::LEN), data, spl_token::id());
+        key
+    }
+
+    pub fn token_amount(&self, key: &Pubkey) -> u64 {
+        Account::unpack(&self.get(key).data.borrow()).unwrap().amount
+    }
+
+    // Metaplex metadata of the non-fungible token with the master edition
+    pub fn add_nft_metadata(&mut self, mint: &Pubkey, update_authority: &Pubkey, name: &str, symbol: &str, uri: &str) {
+        let metadata = Metadata {
+            key: Key::MetadataV1,
+            update_authority: *update_authority,
+            mint: *mint,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(TokenStandard::NonFungible),
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        };
+
+        let edition = MasterEdition {
+            key: Key::MasterEditionV2,
+            supply: 0,
+            max_supply: Some(0),
+        };
+
+        self.add(Metadata::find_pda(mint).0, 1, metadata.try_to_vec().unwrap(), mpl_token_metadata::ID);
+        self.add(MasterEdition::find_pda(mint).0, 1, edition.try_to_vec().unwrap(), mpl_token_metadata::ID);
+    }
+
+    // Bubblegum tree delegated to the bridge admin, the Merkle tree itself is not read by the bridge
+    pub fn add_bridge_tree(&mut self, bridge_admin_key: &Pubkey, num_minted: u64) -> Pubkey {
+        let merkle_tree = Pubkey::new_unique();
+        let tree_config = TreeConfig {
+            discriminator: [0; 8],
+            tree_creator: *bridge_admin_key,
+            tree_delegate: *bridge_admin_key,
+            total_mint_capacity: 1 << 14,
+            num_minted,
+            is_public: false,
+            is_decompressible: DecompressibleState::Disabled,
+        };
+
+        self.add(TreeConfig::find_pda(&merkle_tree).0, 1, tree_config.try_to_vec().unwrap(), mpl_bubblegum::ID);
+        self.add(merkle_tree, 1, Vec::new(), mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID);
+        merkle_tree
+    }
+}
+
+fn commission_admin(bridge_admin_key: &Pubkey, commission_program: &Pubkey) -> Option<Pubkey> {
+    Pubkey::create_program_address(&[lib::COMMISSION_ADMIN_PDA_SEED.as_bytes(), bridge_admin_key.as_ref()], commission_program).ok()
+}
+
+// Commission program whose admin address of the bridge admin is off the curve, as the bridge derives it without a bump
+fn commission_program(bridge_admin_key: &Pubkey) -> Pubkey {
+    (0..=u8::MAX)
+        .map(|i| Pubkey::new_from_array([i; 32]))
+        .find(|program| commission_admin(bridge_admin_key, program).is_some())
+        .unwrap()
+}
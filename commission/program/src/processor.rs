@@ -11,10 +11,9 @@ use borsh::{
 use spl_token::instruction::transfer;
 use spl_associated_token_account::get_associated_token_address;
 use spl_associated_token_account::instruction::create_associated_token_account;
-use solana_program::secp256k1_recover::SECP256K1_PUBLIC_KEY_LENGTH;
 use lib::merkle::get_merkle_root;
 use crate::merkle::Content;
-use lib::ecdsa::verify_ecdsa_signature;
+use lib::ecdsa::EcdsaSignature;
 use lib::instructions::commission::{CommissionInstruction, CommissionTokenArg};
 use lib::error::LibError;
 use bridge::state::BridgeAdmin;
@@ -36,19 +35,19 @@ pub fn process_instruction<'a>(
         }
         CommissionInstruction::AddFeeToken(args) => {
             msg!("Instruction: Add fee token");
-            process_add_token(program_id, accounts, args.signature, args.recovery_id, args.path, args.token)
+            process_add_token(program_id, accounts, args.signatures, args.path, args.token)
         }
         CommissionInstruction::RemoveFeeToken(args) => {
             msg!("Instruction: Remove fee token");
-            process_remove_token(program_id, accounts, args.signature, args.recovery_id, args.path, args.token)
+            process_remove_token(program_id, accounts, args.signatures, args.path, args.token)
         }
         CommissionInstruction::UpdateFeeToken(args) => {
             msg!("Instruction: Update fee token");
-            process_update_token(program_id, accounts, args.signature, args.recovery_id, args.path, args.token)
+            process_update_token(program_id, accounts, args.signatures, args.path, args.token)
         }
         CommissionInstruction::Withdraw(args) => {
             msg!("Instruction: Withdraw collected tokens");
-            process_withdraw(program_id, accounts,  args.signature, args.recovery_id, args.path, args.token, args.withdraw_amount)
+            process_withdraw(program_id, accounts,  args.signatures, args.path, args.token, args.withdraw_amount)
         }
     }
 }
@@ -177,8 +176,7 @@ pub fn process_charge_commission<'a>(
 pub fn process_add_token<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
-    signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    signatures: Vec<EcdsaSignature>,
     path: Vec<[u8; 32]>,
     token: CommissionTokenArg,
 ) -> ProgramResult {
@@ -186,6 +184,7 @@ pub fn process_add_token<'a>(
 
     let commission_admin_info = next_account_info(account_info_iter)?;
     let bridge_admin_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
 
     let commission_key = Pubkey::create_program_address(&[lib::COMMISSION_ADMIN_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()], &program_id)?;
     if commission_key != *commission_admin_info.key {
@@ -211,7 +210,7 @@ pub fn process_add_token<'a>(
     );
 
     let root = get_merkle_root(content.hash(), &path)?;
-    verify_ecdsa_signature(root.as_slice(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
 
     commission_admin.add_token_nonce += 1;
     commission_admin.acceptable_tokens.push(CommissionToken::from(&token));
@@ -223,8 +222,7 @@ pub fn process_add_token<'a>(
 pub fn process_remove_token<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
-    signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    signatures: Vec<EcdsaSignature>,
     path: Vec<[u8; 32]>,
     token: CommissionTokenArg,
 ) -> ProgramResult {
@@ -232,6 +230,7 @@ pub fn process_remove_token<'a>(
 
     let commission_admin_info = next_account_info(account_info_iter)?;
     let bridge_admin_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
 
     let commission_key = Pubkey::create_program_address(&[lib::COMMISSION_ADMIN_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()], &program_id)?;
     if commission_key != *commission_admin_info.key {
@@ -256,7 +255,7 @@ pub fn process_remove_token<'a>(
         CommissionToken::from(&token),
     );
     let root = get_merkle_root(content.hash(), &path)?;
-    verify_ecdsa_signature(root.as_slice(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
 
     let token_to_remove = CommissionToken::from(&token);
     for i in 0..commission_admin.acceptable_tokens.len() {
@@ -275,8 +274,7 @@ pub fn process_remove_token<'a>(
 pub fn process_update_token<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
-    signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    signatures: Vec<EcdsaSignature>,
     path: Vec<[u8; 32]>,
     token: CommissionTokenArg,
 ) -> ProgramResult {
@@ -284,6 +282,7 @@ pub fn process_update_token<'a>(
 
     let commission_admin_info = next_account_info(account_info_iter)?;
     let bridge_admin_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
 
     let commission_key = Pubkey::create_program_address(&[lib::COMMISSION_ADMIN_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()], &program_id)?;
     if commission_key != *commission_admin_info.key {
@@ -308,7 +307,7 @@ pub fn process_update_token<'a>(
         CommissionToken::from(&token),
     );
     let root = get_merkle_root(content.hash(), &path)?;
-    verify_ecdsa_signature(root.as_slice(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
 
     let token_to_update = CommissionToken::from(&token);
     for i in 0..commission_admin.acceptable_tokens.len() {
@@ -328,8 +327,7 @@ pub fn process_update_token<'a>(
 pub fn process_withdraw<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
-    signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    signatures: Vec<EcdsaSignature>,
     path: Vec<[u8; 32]>,
     token: CommissionTokenArg,
     withdraw_amount: u64,
@@ -341,6 +339,7 @@ pub fn process_withdraw<'a>(
     let receiver_info = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
 
     let commission_key = Pubkey::create_program_address(&[lib::COMMISSION_ADMIN_PDA_SEED.as_bytes(), bridge_admin_info.key.as_ref()], &program_id)?;
     if commission_key != *commission_admin_info.key {
@@ -365,7 +364,7 @@ pub fn process_withdraw<'a>(
         CommissionToken::from(&token),
     );
     let root = get_merkle_root(content.hash(), &path)?;
-    verify_ecdsa_signature(root.as_slice(), signature.as_slice(), recovery_id, bridge_admin.public_key)?;
+    bridge_admin.signers.verify(root.as_slice(), &signatures, instruction_sysvar_info)?;
 
     match token.token.into() {
         lib::CommissionToken::Native => {
@@ -1,10 +1,44 @@
-use solana_program::secp256k1_recover::{SECP256K1_PUBLIC_KEY_LENGTH, secp256k1_recover, Secp256k1Pubkey};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::secp256k1_recover::{SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH, secp256k1_recover};
 use solana_program::{
     entrypoint::ProgramResult, hash,
     msg,
 };
 use solana_program::program_error::ProgramError;
 use crate::error::LibError;
+use crate::instructions::InstructionValidation;
+use crate::signers::validate_signer_keys;
+
+pub const MAX_SIGNERS_COUNT: usize = 10;
+pub const SIGNER_SET_SIZE: usize = 4 + MAX_SIGNERS_COUNT * SECP256K1_PUBLIC_KEY_LENGTH + 1;
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct EcdsaSignature {
+    pub signature: [u8; SECP256K1_SIGNATURE_LENGTH],
+    pub recovery_id: u8,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SignerSet {
+    // ECDSA public keys
+    pub public_keys: Vec<[u8; SECP256K1_PUBLIC_KEY_LENGTH]>,
+    // Number of distinct signers required
+    pub threshold: u8,
+}
+
+impl SignerSet {
+    pub fn verify(&self, hash: &[u8], signatures: &[EcdsaSignature]) -> ProgramResult {
+        verify_ecdsa_signatures(hash, signatures, self)
+    }
+}
+
+impl InstructionValidation for SignerSet {
+    fn validate(&self) -> ProgramResult {
+        validate_signer_keys(&self.public_keys, self.threshold)
+    }
+}
 
 pub fn verify_ecdsa_signature(hash: &[u8], sig: &[u8], reid: u8, target_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH]) -> ProgramResult {
     let recovered_key = secp256k1_recover(hash, reid, sig);
@@ -24,3 +58,36 @@ pub fn verify_ecdsa_signature(hash: &[u8], sig: &[u8], reid: u8, target_key: [u8
     Ok(())
 }
 
+// Checks that at least `threshold` distinct signers from the set signed the hash
+pub fn verify_ecdsa_signatures(hash: &[u8], signatures: &[EcdsaSignature], signers: &SignerSet) -> ProgramResult {
+    if signatures.is_empty() {
+        return Err(LibError::EmptySignatures.into());
+    }
+
+    let mut verified: Vec<[u8; SECP256K1_PUBLIC_KEY_LENGTH]> = Vec::new();
+
+    for sig in signatures {
+        let key = match secp256k1_recover(hash, sig.recovery_id, sig.signature.as_slice()) {
+            Ok(key) => key.0,
+            Err(_) => return Err(LibError::InvalidSignature.into()),
+        };
+
+        msg!("Recovered public key from signature: {}", bs58::encode(key.as_ref()).into_string().as_str());
+
+        if !signers.public_keys.contains(&key) {
+            return Err(LibError::WrongSignature.into());
+        }
+
+        if verified.contains(&key) {
+            return Err(LibError::DuplicateSignature.into());
+        }
+
+        verified.push(key);
+    }
+
+    if verified.len() < signers.threshold as usize {
+        return Err(LibError::ThresholdNotReached.into());
+    }
+
+    Ok(())
+}
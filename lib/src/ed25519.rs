@@ -0,0 +1,159 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use crate::error::LibError;
+use crate::instructions::InstructionValidation;
+use crate::signers::validate_signer_keys;
+
+pub const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
+
+// See `Ed25519SignatureOffsets` in the Solana SDK
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_SIZE: usize = 14;
+// Instruction index that points to the Ed25519 instruction itself
+const CURRENT_INSTRUCTION_INDEX: u16 = u16::MAX;
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Ed25519SignerSet {
+    // Ed25519 public keys
+    pub public_keys: Vec<Pubkey>,
+    // Number of distinct signers required
+    pub threshold: u8,
+}
+
+impl Ed25519SignerSet {
+    pub fn verify(&self, hash: &[u8], instruction_sysvar_info: &AccountInfo) -> ProgramResult {
+        verify_ed25519_signatures(hash, instruction_sysvar_info, self)
+    }
+}
+
+impl InstructionValidation for Ed25519SignerSet {
+    fn validate(&self) -> ProgramResult {
+        validate_signer_keys(&self.public_keys, self.threshold)
+    }
+}
+
+// Checks that Ed25519 program instructions before the current one verified
+// signatures of the hash by at least `threshold` distinct signers from the set
+pub fn verify_ed25519_signatures(hash: &[u8], instruction_sysvar_info: &AccountInfo, signers: &Ed25519SignerSet) -> ProgramResult {
+    let current_index = load_current_index_checked(instruction_sysvar_info)?;
+    let mut verified: Vec<Pubkey> = Vec::new();
+
+    for index in 0..current_index {
+        let instruction = load_instruction_at_checked(index as usize, instruction_sysvar_info)?;
+        if instruction.program_id != ed25519_program::id() {
+            continue;
+        }
+
+        for (key, message) in get_signed_messages(instruction.data.as_slice())? {
+            if message != hash || !signers.public_keys.contains(&key) || verified.contains(&key) {
+                continue;
+            }
+
+            msg!("Verified Ed25519 signer: {}", key);
+            verified.push(key);
+        }
+    }
+
+    if verified.len() < signers.threshold as usize {
+        return Err(LibError::ThresholdNotReached.into());
+    }
+
+    Ok(())
+}
+
+// Returns public keys and messages verified by the Ed25519 program instruction.
+// Only self-contained instructions are accepted.
+fn get_signed_messages(data: &[u8]) -> Result<Vec<(Pubkey, &[u8])>, ProgramError> {
+    let count = *data.first().ok_or(ProgramError::from(LibError::WrongEd25519Instruction))? as usize;
+    let mut result = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_SIZE;
+        let offsets = data.get(start..start + SIGNATURE_OFFSETS_SIZE).ok_or(ProgramError::from(LibError::WrongEd25519Instruction))?;
+        let read = |pos: usize| u16::from_le_bytes([offsets[pos], offsets[pos + 1]]);
+
+        let signature_instruction_index = read(2);
+        let public_key_offset = read(4) as usize;
+        let public_key_instruction_index = read(6);
+        let message_offset = read(8) as usize;
+        let message_size = read(10) as usize;
+        let message_instruction_index = read(12);
+
+        if signature_instruction_index != CURRENT_INSTRUCTION_INDEX ||
+            public_key_instruction_index != CURRENT_INSTRUCTION_INDEX ||
+            message_instruction_index != CURRENT_INSTRUCTION_INDEX {
+            return Err(LibError::WrongEd25519Instruction.into());
+        }
+
+        let key = data.get(public_key_offset..public_key_offset + ED25519_PUBLIC_KEY_LENGTH).ok_or(ProgramError::from(LibError::WrongEd25519Instruction))?;
+        let message = data.get(message_offset..message_offset + message_size).ok_or(ProgramError::from(LibError::WrongEd25519Instruction))?;
+
+        result.push((Pubkey::try_from(key).map_err(|_| ProgramError::from(LibError::WrongEd25519Instruction))?, message));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER_SIZE: usize = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SIZE;
+
+    // Self-contained Ed25519 instruction data with one signature: offsets | public key | signature | message
+    fn instruction_data(key: &Pubkey, message: &[u8], instruction_index: u16) -> Vec<u8> {
+        let public_key_offset = HEADER_SIZE as u16;
+        let signature_offset = public_key_offset + ED25519_PUBLIC_KEY_LENGTH as u16;
+        let message_offset = signature_offset + 64;
+
+        let mut data = vec![1u8, 0u8];
+        for value in [
+            signature_offset, instruction_index,
+            public_key_offset, instruction_index,
+            message_offset, message.len() as u16, instruction_index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        data.extend_from_slice(key.as_ref());
+        data.extend_from_slice(&[0u8; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn signed_messages() {
+        let key = Pubkey::new_unique();
+        let message = [7u8; 32];
+        let data = instruction_data(&key, &message, CURRENT_INSTRUCTION_INDEX);
+
+        assert_eq!(get_signed_messages(&data).unwrap(), vec![(key, &message[..])]);
+        assert_eq!(get_signed_messages(&[0u8, 0u8]).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn signed_messages_from_other_instruction() {
+        let data = instruction_data(&Pubkey::new_unique(), &[7u8; 32], 0);
+        assert_eq!(get_signed_messages(&data), Err(LibError::WrongEd25519Instruction.into()));
+    }
+
+    #[test]
+    fn signed_messages_out_of_bounds() {
+        let key = Pubkey::new_unique();
+        let data = instruction_data(&key, &[7u8; 32], CURRENT_INSTRUCTION_INDEX);
+
+        // Empty data, truncated offsets and truncated message
+        assert_eq!(get_signed_messages(&[]), Err(LibError::WrongEd25519Instruction.into()));
+        assert_eq!(get_signed_messages(&data[..HEADER_SIZE - 1]), Err(LibError::WrongEd25519Instruction.into()));
+        assert_eq!(get_signed_messages(&data[..data.len() - 1]), Err(LibError::WrongEd25519Instruction.into()));
+    }
+}
@@ -100,6 +100,93 @@ pub enum LibError {
     /// 30 Token is not supported yet
     #[error("Not supported")]
     NotSupported,
+    /// 31 Log data is not the expected event
+    #[error("Wrong event")]
+    WrongEvent,
+    /// 32 Bundle data provided without seed or vice versa
+    #[error("Wrong bundle")]
+    WrongBundle,
+    /// 33 Wrong target program for the call
+    #[error("Wrong target program")]
+    WrongTargetProgram,
+    /// 34 Network is not registered in the network registry
+    #[error("Unknown network")]
+    UnknownNetwork,
+    /// 35 Receiver address does not match the network format
+    #[error("Wrong receiver address")]
+    WrongReceiverAddress,
+    /// 36 Operation is paused
+    #[error("Paused")]
+    Paused,
+    /// 37 Withdrawal amount exceeds the current rate limit
+    #[error("Rate limit exceeded")]
+    RateLimitExceeded,
+    /// 38 Deposit amount is out of the allowed range
+    #[error("Wrong deposit amount")]
+    WrongDepositAmount,
+    /// 39 Token is rejected by the token policy
+    #[error("Token not allowed")]
+    TokenNotAllowed,
+    /// 40 Several signatures of the same signer
+    #[error("Duplicate signature")]
+    DuplicateSignature,
+    /// 41 Not enough signatures of the signer set
+    #[error("Threshold not reached")]
+    ThresholdNotReached,
+    /// 42 Signer set is empty, has duplicated keys or wrong threshold
+    #[error("Wrong signer set")]
+    WrongSignerSet,
+    /// 43 Instructions sysvar is required in its fixed slot of every signed instruction
+    #[error("Missing instructions sysvar")]
+    MissingInstructionsSysvar,
+    /// 44 Ed25519 program instruction has wrong layout or references other instructions
+    #[error("Wrong Ed25519 instruction")]
+    WrongEd25519Instruction,
+    /// 45 SignedRoot account is missing, not initialized or does not match the Merkle root and signers
+    #[error("Wrong signed root")]
+    WrongSignedRoot,
+    /// 46 Relayer fee should be less than the withdrawn amount and is not supported for NFT
+    #[error("Wrong relayer fee")]
+    WrongRelayerFee,
+    /// 47 Withdrawal index is required in the bitmap withdraw mode and not allowed in the receipt mode
+    #[error("Wrong withdraw mode")]
+    WrongWithdrawMode,
+    /// 48 Token program should be SPL Token or Token-2022 and own the mint
+    #[error("Wrong token program")]
+    WrongTokenProgram,
+    /// 49 Mint has extension that is not supported by the bridge
+    #[error("Unsupported mint extension")]
+    UnsupportedMintExtension,
+    /// 50 Bubblegum tree for wrapped compressed NFTs should be delegated to the bridge admin
+    #[error("Wrong Merkle tree")]
+    WrongMerkleTree,
+    /// 51 Collection is not supported for the token or does not match the signed one
+    #[error("Wrong collection")]
+    WrongCollection,
+    /// 52 Tokens wrapped before the metadata updates were introduced have immutable metadata
+    #[error("Metadata is immutable")]
+    ImmutableMetadata,
+    /// 53 Secp256k1 signed operations require at least one signature
+    #[error("Empty signatures")]
+    EmptySignatures,
+    /// 54 Admin account already has the current layout
+    #[error("Already migrated")]
+    AlreadyMigrated,
+    /// 55 Admin account has the legacy layout or its SOL is not moved to the vault yet
+    #[error("Not migrated")]
+    NotMigrated,
+    /// 56 SOL vault would not be rent-exempt after the migration
+    #[error("Insufficient SOL vault rent")]
+    InsufficientVaultRent,
+    /// 57 Account is not the rent payer stored in the Withdraw account
+    #[error("Wrong rent payer")]
+    WrongRentPayer,
+    /// 58 Accounts passed to the target program do not match the signed call
+    #[error("Wrong call accounts")]
+    WrongCallAccounts,
+    /// 59 Leaf of the wrapped compressed NFT is already minted and not deposited back
+    #[error("Already minted")]
+    AlreadyMinted,
 }
 
 
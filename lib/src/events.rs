@@ -0,0 +1,79 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    entrypoint::ProgramResult,
+    log::sol_log_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use crate::error::LibError;
+use crate::TokenType;
+
+// First `Program data:` field of every deposit event log
+pub const DEPOSIT_EVENT_TAG: &[u8] = b"deposit";
+// First `Program data:` field of every outbound message event log
+pub const MESSAGE_EVENT_TAG: &[u8] = b"message";
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct DepositEvent {
+    // Sequence number of the deposit in the BridgeAdmin
+    pub deposit_id: u64,
+    pub token_type: TokenType,
+    // Empty if is native
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub network_to: String,
+    pub receiver_address: String,
+    pub sender: Pubkey,
+    pub bundle_data: Option<Vec<u8>>,
+    pub bundle_seed: Option<[u8; 32]>,
+    // keccak(bundle_seed | sender), see `get_bundle_salt`
+    pub bundle_salt: Option<[u8; 32]>,
+}
+
+impl DepositEvent {
+    pub fn emit(&self) -> ProgramResult {
+        emit_event(DEPOSIT_EVENT_TAG, self)
+    }
+
+    /// Decodes event from the `Program data:` log fields (already base64-decoded).
+    pub fn decode(fields: &[&[u8]]) -> Result<Self, ProgramError> {
+        decode_event(DEPOSIT_EVENT_TAG, fields)
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct MessageEvent {
+    // Sequence number of the message in the BridgeAdmin
+    pub message_id: u64,
+    pub sender: Pubkey,
+    pub network_to: String,
+    pub target_address: String,
+    pub payload: Vec<u8>,
+}
+
+impl MessageEvent {
+    pub fn emit(&self) -> ProgramResult {
+        emit_event(MESSAGE_EVENT_TAG, self)
+    }
+
+    /// Decodes event from the `Program data:` log fields (already base64-decoded).
+    pub fn decode(fields: &[&[u8]]) -> Result<Self, ProgramError> {
+        decode_event(MESSAGE_EVENT_TAG, fields)
+    }
+}
+
+pub fn emit_event<T: BorshSerialize>(tag: &[u8], event: &T) -> ProgramResult {
+    let data = event.try_to_vec()?;
+    sol_log_data(&[tag, data.as_slice()]);
+    Ok(())
+}
+
+pub fn decode_event<T: BorshDeserialize>(tag: &[u8], fields: &[&[u8]]) -> Result<T, ProgramError> {
+    if fields.len() != 2 || fields[0] != tag {
+        return Err(LibError::WrongEvent.into());
+    }
+
+    Ok(T::try_from_slice(fields[1])?)
+}
@@ -1,28 +1,38 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use mpl_token_metadata::types::DataV2;
+use mpl_bubblegum::{accounts::TreeConfig, types::MetadataArgs};
+use mpl_token_metadata::{accounts::{MasterEdition, Metadata, TokenRecord}, types::DataV2};
 use solana_program::{
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     sysvar,
 };
-use solana_program::secp256k1_recover::{SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH};
-use spl_associated_token_account::get_associated_token_address;
+use crate::ecdsa::EcdsaSignature;
+use crate::signers::SignerScheme;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 use crate::instructions::InstructionValidation;
 use std::mem::size_of;
 use crate::error::LibError;
 use crate::TokenType;
+use crate::networks::Network;
 
 pub const MAX_NETWORKS_SIZE: usize = 20;
 pub const MAX_ADDRESS_SIZE: usize = 100;
 pub const MAX_TOKEN_ID_SIZE: usize = 100;
 pub const MAX_TX_SIZE: usize = 100;
+pub const MAX_BUNDLE_SIZE: usize = 500;
+pub const MAX_PAYLOAD_SIZE: usize = 500;
+pub const MAX_POLICY_TOKENS_COUNT: usize = 100;
+pub const MAX_BATCH_SIZE: usize = 8;
+pub const MAX_CALL_ACCOUNTS_COUNT: usize = 16;
+// Number of withdrawal indexes in one WithdrawBitmap shard
+pub const WITHDRAW_BITMAP_BITS: u64 = 8192;
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct InitializeAdminArgs {
-    // ECDSA public key
-    pub public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
+    // Signer set with the signature scheme
+    pub signers: SignerScheme,
     // Admin account seeds (also public)
     pub seeds: [u8; 32],
     pub commission_program: Pubkey,
@@ -31,11 +41,10 @@ pub struct InitializeAdminArgs {
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct TransferOwnershipArgs {
-    // New ECDSA public key
-    pub new_public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    // Signature of new_public_key by old public key
-    pub signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    pub recovery_id: u8,
+    // New signer set with the signature scheme
+    pub new_signers: SignerScheme,
+    // Signatures of the new signer set hash by the threshold of current signers
+    pub signatures: Vec<EcdsaSignature>,
     // Admin account seeds
     pub seeds: [u8; 32],
 }
@@ -49,6 +58,8 @@ pub struct DepositNativeArgs {
     pub seeds: [u8; 32],
     pub bundle_data: Option<Vec<u8>>,
     pub bundle_seed: Option<[u8; 32]>,
+    // Unwrap SOL by closing the owner wSOL associated account before the deposit
+    pub wrapped_sol: bool,
 }
 
 #[repr(C)]
@@ -74,6 +85,48 @@ pub struct DepositNFTArgs {
     pub bundle_seed: Option<[u8; 32]>,
 }
 
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct CompressedLeaf {
+    // Current root of the Bubblegum tree, proof nodes are passed after the instruction accounts
+    pub root: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+    // Leaf metadata, its hash is verified by Bubblegum
+    pub metadata: MetadataArgs,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct DepositCNFTArgs {
+    pub network_to: String,
+    pub receiver_address: String,
+    pub seeds: [u8; 32],
+    pub leaf: CompressedLeaf,
+    // Token seed of the wrapped asset, its leaf is burned instead of the transfer
+    pub token_seed: Option<[u8; 32]>,
+    pub bundle_data: Option<Vec<u8>>,
+    pub bundle_seed: Option<[u8; 32]>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WithdrawCNFTArgs {
+    // Default: hash of tx | event_id | network_from
+    pub origin: [u8; 32],
+    // Signatures for the Merkle root or the SignedRoot account
+    pub proof: RootProof,
+    // Merkle path
+    pub path: Vec<[u8; 32]>,
+    pub seeds: [u8; 32],
+    // Leaf owned by the bridge admin to be transferred to the receiver
+    pub leaf: Option<CompressedLeaf>,
+    // Wrapped asset to be minted into the bridge tree, used instead of the leaf
+    pub token_seed: Option<[u8; 32]>,
+    pub signed_meta: Option<SignedMetadata>,
+    pub bitmap_index: Option<u64>,
+}
+
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct SignedMetadata {
@@ -81,6 +134,25 @@ pub struct SignedMetadata {
     pub symbol: String,
     pub uri: String,
     pub decimals: u8,
+    // Collection mint created by MintCollection, verified for wrapped NFTs. Not supported for other tokens.
+    pub collection: Option<Pubkey>,
+    // Version 2 metadata of wrapped NFTs, signed after the version tag. Empty in version 1.
+    pub royalties: Option<SignedRoyalties>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SignedRoyalties {
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<SignedCreator>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SignedCreator {
+    // Solana address of the creator, stays unverified in the Metaplex metadata
+    pub address: Pubkey,
+    pub share: u8,
 }
 
 #[repr(C)]
@@ -89,14 +161,302 @@ pub struct WithdrawArgs {
     // Default: hash of tx | event_id | network_from
     pub origin: [u8; 32],
     pub amount: u64,
-    // Signature for the Merkle root
-    pub signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    pub recovery_id: u8,
+    // Signatures for the Merkle root or the SignedRoot account
+    pub proof: RootProof,
     // Merkle path
     pub path: Vec<[u8; 32]>,
     pub seeds: [u8; 32],
     pub token_seed: Option<[u8; 32]>,
     pub signed_meta: Option<SignedMetadata>,
+    // Part of the amount paid to the fee payer (relayer), should be signed. Not supported for NFT.
+    pub relayer_fee: Option<u64>,
+    // Sequential withdrawal index, should be signed. Required in the bitmap withdraw mode.
+    pub bitmap_index: Option<u64>,
+    // Deliver SOL as wSOL to the owner associated account. Only for SOL.
+    pub wrapped_sol: bool,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub enum RootProof {
+    // Signatures of the Merkle root by the bridge admin signers
+    Signatures(Vec<EcdsaSignature>),
+    // SignedRoot account created by SubmitRoot for the Merkle root.
+    // The account should be passed among the instruction accounts (after the listed ones).
+    SignedRoot(Pubkey),
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SubmitRootArgs {
+    pub root: [u8; 32],
+    // Signatures of the root by the bridge admin signers
+    pub signatures: Vec<EcdsaSignature>,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WithdrawTransfer {
+    pub token_type: TokenType,
+    // Default: hash of tx | event_id | network_from
+    pub origin: [u8; 32],
+    pub amount: u64,
+    // Merkle path to the root signed for the batch
+    pub path: Vec<[u8; 32]>,
+    pub token_seed: Option<[u8; 32]>,
+    pub signed_meta: Option<SignedMetadata>,
+    pub relayer_fee: Option<u64>,
+    pub bitmap_index: Option<u64>,
+    pub wrapped_sol: bool,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WithdrawBatchArgs {
+    // Proof for the Merkle root shared by all transfers
+    pub proof: RootProof,
+    pub transfers: Vec<WithdrawTransfer>,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct TargetCall {
+    // Program to be called after the withdrawal
+    pub program: Pubkey,
+    // Accounts passed to the target program after the call authority, never as signers
+    pub accounts: Vec<TargetAccount>,
+    // Instruction data for the target program
+    pub payload: Vec<u8>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct TargetAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WithdrawAndCallArgs {
+    pub token_type: TokenType,
+    pub withdraw: WithdrawArgs,
+    pub call: TargetCall,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SendMessageArgs {
+    pub network_to: String,
+    // Receiver of the message on the destination network
+    pub target_address: String,
+    pub payload: Vec<u8>,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct ReceiveMessageArgs {
+    // Default: hash of tx | event_id | network_from
+    pub origin: [u8; 32],
+    // Sender of the message on the source network
+    pub sender: String,
+    pub payload: Vec<u8>,
+    // Signature for the Merkle root
+    pub signatures: Vec<EcdsaSignature>,
+    // Merkle path
+    pub path: Vec<[u8; 32]>,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct InitializeNetworkRegistryArgs {
+    // Signature for the Merkle root
+    pub signatures: Vec<EcdsaSignature>,
+    // Merkle path
+    pub path: Vec<[u8; 32]>,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct MigrateSolVaultArgs {
+    // Signature for the Merkle root
+    pub signatures: Vec<EcdsaSignature>,
+    // Merkle path
+    pub path: Vec<[u8; 32]>,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct MigrateAdminArgs {
+    // Signature of the migration operation content by the legacy ECDSA public key
+    pub signature: EcdsaSignature,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct NetworkArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    pub network: Network,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Default)]
+pub struct PauseState {
+    // Bit masks of paused token types, see `TokenType::mask`
+    pub deposits: u8,
+    pub withdrawals: u8,
+}
+
+impl PauseState {
+    pub fn check_deposit(&self, token: &TokenType) -> ProgramResult {
+        if self.deposits & token.mask() != 0 {
+            return Err(LibError::Paused.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn check_withdrawal(&self, token: &TokenType) -> ProgramResult {
+        if self.withdrawals & token.mask() != 0 {
+            return Err(LibError::Paused.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SetPauseArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    // New pause state, replaces the current one
+    pub pause: PauseState,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SetRateLimitArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    // Empty if is native
+    pub mint: Option<Pubkey>,
+    // Maximum amount that can be withdrawn at once
+    pub capacity: u64,
+    // Amount that becomes available for withdrawal every second
+    pub refill_per_second: u64,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SetDepositLimitArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    // Empty if is native
+    pub mint: Option<Pubkey>,
+    pub min_amount: u64,
+    pub max_amount: u64,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Default)]
+pub enum TokenPolicyMode {
+    // Every token can be deposited
+    #[default]
+    Disabled,
+    // Only listed tokens can be deposited
+    Allowlist,
+    // Listed tokens can not be deposited
+    Denylist,
+}
+
+impl From<&TokenPolicyMode> for u8 {
+    fn from(mode: &TokenPolicyMode) -> u8 {
+        match mode {
+            TokenPolicyMode::Disabled => 0,
+            TokenPolicyMode::Allowlist => 1,
+            TokenPolicyMode::Denylist => 2,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy, Default)]
+pub enum WithdrawMode {
+    // Every withdrawal creates the Withdraw account for its origin
+    #[default]
+    Receipt,
+    // Every withdrawal sets the bit of its signed index in the WithdrawBitmap shard
+    Bitmap,
+}
+
+impl From<&WithdrawMode> for u8 {
+    fn from(mode: &WithdrawMode) -> u8 {
+        match mode {
+            WithdrawMode::Receipt => 0,
+            WithdrawMode::Bitmap => 1,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SetWithdrawModeArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    pub mode: WithdrawMode,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct CloseWithdrawArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    // Origin of the Withdraw account to close
+    pub origin: [u8; 32],
+    // Withdrawal index assigned to the origin in the bitmap
+    pub bitmap_index: u64,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SetTokenPolicyArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    pub mode: TokenPolicyMode,
+    // New list of token mints, replaces the current one
+    pub mints: Vec<Pubkey>,
+    pub seeds: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct UpdateWrappedMetadataArgs {
+    pub signatures: Vec<EcdsaSignature>,
+    pub path: Vec<[u8; 32]>,
+    // Seed of the wrapped mint
+    pub token_seed: [u8; 32],
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    // Empty to remove creators
+    pub creators: Vec<SignedCreator>,
+    pub seeds: [u8; 32],
 }
 
 #[repr(C)]
@@ -125,6 +485,7 @@ pub enum BridgeInstruction {
     ///   1. `[writable,signer]` The fee payer
     ///   2. `[]` System program
     ///   3. `[]` Rent sysvar
+    ///   4. `[writable]` The SOL vault account, funded with its rent-exempt reserve
     InitializeAdmin(InitializeAdminArgs),
 
     /// Change admin in BridgeAdmin.
@@ -132,7 +493,7 @@ pub enum BridgeInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` The BridgeAdmin account
-    ///
+    ///   1. `[]` Instructions sysvar
     TransferOwnership(TransferOwnershipArgs),
 
     /// Make SOL deposit on bridge.
@@ -143,14 +504,21 @@ pub enum BridgeInstruction {
     ///   1. `[writable,signer]` The owner account
     ///   2. `[]` System program
     ///   3. `[]` Rent sysvar
-    ///   5. `[]` Sysvar
+    ///   4. `[]` Instructions sysvar
+    ///   5. `[]` The NetworkRegistry account
+    ///   6. `[]` The DepositLimit account (may be empty)
+    ///   7. `[writable]` The SOL vault account
+    ///   8. `[writable]` The owner wSOL associated account if `wrapped_sol` is set
+    ///   9. `[]` Token program id if `wrapped_sol` is set
+    ///
+    ///   N. `[writable]` The new Deposit account of the current deposit nonce (Optional)
     DepositNative(DepositNativeArgs),
 
     /// Make FT deposit on bridge.
     ///
     /// Accounts expected by this instruction:
     ///
-    ///   0. `[]` The BridgeAdmin account
+    ///   0. `[writable]` The BridgeAdmin account
     ///   1. `[writable]` The token mint account
     ///   2. `[writable]` The owner token associated account
     ///   3. `[writable]` The bridge token account
@@ -158,15 +526,19 @@ pub enum BridgeInstruction {
     ///   5. `[]` Token program id
     ///   6. `[]` System program
     ///   7. `[]` Rent sysvar
-    ///   8. `[]` Sysvar
+    ///   8. `[]` Instructions sysvar
     ///   9. `[]` Associated token program
+    ///   10. `[]` The NetworkRegistry account
+    ///   11. `[]` The DepositLimit account (may be empty)
+    ///   12. `[]` The TokenPolicy account (may be empty)
+    ///   13. `[writable]` The new Deposit account of the current deposit nonce (Optional)
     DepositFT(DepositFTArgs),
 
     /// Make NFT deposit on bridge.
     ///
     /// Accounts expected by this instruction:
     ///
-    ///   0. `[]` The BridgeAdmin account
+    ///   0. `[writable]` The BridgeAdmin account
     ///   1. `[writable]` The token mint account
     ///   2. `[writable]` The owner token associated account
     ///   3. `[writable]` The bridge token account
@@ -174,8 +546,15 @@ pub enum BridgeInstruction {
     ///   5. `[]` Token program id
     ///   6. `[]` System program
     ///   7. `[]` Rent sysvar
-    ///   8. `[]` Sysvar
+    ///   8. `[]` Instructions sysvar
     ///   9. `[]` Associated token program
+    ///   10. `[]` The NetworkRegistry account
+    ///   11. `[]` The TokenPolicy account (may be empty)
+    ///   12. `[writable]` The token metadata account (may be empty)
+    ///
+    ///   13..19. Programmable NFT accounts if the token standard is programmable, see `get_programmable_accounts`
+    ///
+    ///   N. `[writable]` The new Deposit account of the current deposit nonce (Optional)
     DepositNFT(DepositNFTArgs),
 
     /// Make NFT withdraw from bridge.
@@ -183,10 +562,18 @@ pub enum BridgeInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` The BridgeAdmin account
-    ///   1. `[writable,signer]` The owner account
-    ///   2. `[writable]` The new Withdraw account
-    ///   3. `[]` System program
-    ///   4. `[]` Rent sysvar
+    ///   1. `[writable]` The owner (receiver) account
+    ///   2. `[writable,signer]` The fee payer (relayer), receives the relayer fee
+    ///   3. `[writable]` The new Withdraw account (WithdrawBitmap account in the bitmap mode)
+    ///   4. `[]` System program
+    ///   5. `[]` Rent sysvar
+    ///   6. `[writable]` The RateLimit account (may be empty)
+    ///   7. `[writable]` The SOL vault account
+    ///   8. `[]` Instructions sysvar
+    ///   9. `[writable]` The owner wSOL associated account if `wrapped_sol` is set (created if empty)
+    ///   10. `[]` The native mint if `wrapped_sol` is set
+    ///   11. `[]` Token program id if `wrapped_sol` is set
+    ///   12. `[]` Associated token program if `wrapped_sol` is set
     WithdrawNative(WithdrawArgs),
 
     /// Make FT withdraw from bridge.
@@ -196,15 +583,19 @@ pub enum BridgeInstruction {
     ///   0. `[]` The BridgeAdmin account
     ///   1. `[writable]` The token mint account
     ///   2. `[writable]` The token metadata account
-    ///   3. `[writable,signer]` The owner account
-    ///   4. `[writable]` The owner token associated account
-    ///   5. `[writable]` The bridge token account
-    ///   6. `[writable]` The new Withdraw account
-    ///   7. `[]` Token program id
-    ///   8. `[]` System program
-    ///   9. `[]` Rent sysvar
-    ///   10. `[]` Metadata program
-    ///   11. `[]` Associated token program
+    ///   3. `[writable]` The owner (receiver) account
+    ///   4. `[writable,signer]` The fee payer (relayer)
+    ///   5. `[writable]` The fee payer token associated account, receives the relayer fee
+    ///   6. `[writable]` The owner token associated account
+    ///   7. `[writable]` The bridge token account
+    ///   8. `[writable]` The new Withdraw account (WithdrawBitmap account in the bitmap mode)
+    ///   9. `[]` Token program id
+    ///   10. `[]` System program
+    ///   11. `[]` Rent sysvar
+    ///   12. `[]` Metadata program
+    ///   13. `[]` Associated token program
+    ///   14. `[writable]` The RateLimit account (may be empty)
+    ///   15. `[]` Instructions sysvar
     WithdrawFT(WithdrawArgs),
 
     /// Make NFT withdraw from bridge.
@@ -214,23 +605,31 @@ pub enum BridgeInstruction {
     ///   0. `[]` The BridgeAdmin account
     ///   1. `[writable]` The token mint account
     ///   2. `[writable]` The token metadata account
-    ///   3. `[writable,signer]` The owner account
-    ///   4. `[writable]` The owner token associated account
-    ///   5. `[writable]` The bridge token account
-    ///   6. `[writable]` The new Withdraw account
-    ///   7. `[]` Token program id
-    ///   8. `[]` System program
-    ///   9. `[]` Rent sysvar
-    ///   10. `[]` Metadata program
-    ///   11. `[]` Associated token program
-    ///   12. `[]` Collection metadata account (Optional)
+    ///   3. `[writable]` The owner (receiver) account
+    ///   4. `[writable,signer]` The fee payer (relayer)
+    ///   5. `[writable]` The owner token associated account
+    ///   6. `[writable]` The bridge token account
+    ///   7. `[writable]` The new Withdraw account (WithdrawBitmap account in the bitmap mode)
+    ///   8. `[]` Token program id
+    ///   9. `[]` System program
+    ///   10. `[]` Rent sysvar
+    ///   11. `[]` Metadata program
+    ///   12. `[]` Associated token program
+    ///   13. `[writable]` The RateLimit account (may be empty)
+    ///   14. `[]` Instructions sysvar
+    ///   15. `[writable]` The master edition account if the token is wrapped (created on the first withdrawal)
+    ///   16. `[]` The collection mint account if the token is wrapped with the signed collection
+    ///   17. `[]` The collection master edition account if the token is wrapped with the signed collection
+    ///
+    ///   N..N+6. Programmable NFT accounts if the token standard is programmable, see `get_programmable_accounts`
+    ///   M. `[writable]` Collection metadata account (Optional)
     WithdrawNFT(WithdrawArgs),
 
     /// Create collection NFT owned by brisge
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` The BridgeAdmin account
-    ///   1. `[writable,signed]` The token mint account
+    ///   1. `[writable]` The token mint account
     ///   2. `[writable]` The bridge token account
     ///   3. `[writable]` The new metadata account
     ///   4. `[writable,signer]` The payer account
@@ -239,39 +638,332 @@ pub enum BridgeInstruction {
     ///   7. `[]` Rent sysvar
     ///   8. `[]` System program
     ///   9. `[]` Associated token program
+    ///   10. `[writable]` The new master edition account
     MintCollection(MintCollectionArgs),
-}
 
+    /// Make withdraw from bridge and call the target program with the signed payload and accounts.
+    /// The target program receives the call authority PDA of the bridge admin and target program
+    /// as a first signer account, so it can check that the call was made by the bridge.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0..N. Accounts for WithdrawNative, WithdrawFT or WithdrawNFT (depends on token type)
+    ///   N+1. `[]` Target program
+    ///   N+2. `[]` The call authority PDA
+    ///   N+3.. Accounts of the signed call in the same order, passed to the target program as non-signers
+    WithdrawAndCall(WithdrawAndCallArgs),
 
-impl InstructionValidation for DepositNativeArgs {
-    fn validate(&self) -> ProgramResult {
-        if self.receiver_address.as_bytes().len() > MAX_ADDRESS_SIZE ||
-            self.network_to.as_bytes().len() > MAX_NETWORKS_SIZE || self.amount <= 0 {
-            return Err(LibError::WrongArgsSize.into());
-        }
+    /// Send message without token transfer.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[writable,signer]` The sender account
+    ///   2. `[]` System program
+    ///   3. `[]` Rent sysvar
+    ///   4. `[]` Instructions sysvar
+    ///   5. `[]` The NetworkRegistry account
+    ///   6. `[writable]` The new Message account (Optional)
+    SendMessage(SendMessageArgs),
 
-        Ok(())
-    }
-}
+    /// Receive signed message and store its receipt.
+    /// Receiver program can read the payload from the receipt account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[]` The receiver account
+    ///   2. `[writable,signer]` The fee payer
+    ///   3. `[writable]` The new MessageReceipt account
+    ///   4. `[]` System program
+    ///   5. `[]` Rent sysvar
+    ///   6. `[]` Instructions sysvar
+    ReceiveMessage(ReceiveMessageArgs),
 
-impl InstructionValidation for DepositFTArgs {
-    fn validate(&self) -> ProgramResult {
-        if self.receiver_address.as_bytes().len() > MAX_ADDRESS_SIZE ||
-            self.network_to.as_bytes().len() > MAX_NETWORKS_SIZE || self.amount <= 0 {
-            return Err(LibError::WrongArgsSize.into());
-        }
+    /// Initialize empty NetworkRegistry that will store supported destination networks.
+    /// Until the registry is initialized, destination networks of deposits and messages are not validated.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[writable]` The NetworkRegistry account to initialize
+    ///   2. `[writable,signer]` The fee payer
+    ///   3. `[]` System program
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` Instructions sysvar
+    InitializeNetworkRegistry(InitializeNetworkRegistryArgs),
 
-        Ok(())
-    }
-}
+    /// Add new supported destination network
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[writable]` The NetworkRegistry account
+    ///   2. `[]` Instructions sysvar
+    AddNetwork(NetworkArgs),
 
-impl InstructionValidation for DepositNFTArgs {
-    fn validate(&self) -> ProgramResult {
-        if self.receiver_address.as_bytes().len() > MAX_ADDRESS_SIZE || self.network_to.as_bytes().len() > MAX_NETWORKS_SIZE {
-            return Err(LibError::WrongArgsSize.into());
-        }
+    /// Remove supported destination network
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[writable]` The NetworkRegistry account
+    ///   2. `[]` Instructions sysvar
+    RemoveNetwork(NetworkArgs),
 
-        Ok(())
+    /// Pause or unpause deposits and withdrawals per token type
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[]` Instructions sysvar
+    SetPause(SetPauseArgs),
+
+    /// Create or update withdrawal rate limit for the token
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[writable]` The RateLimit account
+    ///   2. `[writable,signer]` The fee payer
+    ///   3. `[]` System program
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` Instructions sysvar
+    SetRateLimit(SetRateLimitArgs),
+
+    /// Create or update minimum and maximum deposit amounts for the token
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[writable]` The DepositLimit account
+    ///   2. `[writable,signer]` The fee payer
+    ///   3. `[]` System program
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` Instructions sysvar
+    SetDepositLimit(SetDepositLimitArgs),
+
+    /// Create or replace the token policy for FT and NFT deposits
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[writable]` The TokenPolicy account
+    ///   2. `[writable,signer]` The fee payer
+    ///   3. `[]` System program
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` Instructions sysvar
+    SetTokenPolicy(SetTokenPolicyArgs),
+
+    /// Make several withdrawals from bridge under one signed Merkle root.
+    /// The proof is verified once, every transfer should have the same root.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0..N. Accounts for WithdrawNative, WithdrawFT or WithdrawNFT of the first transfer
+    ///   N+1..M. Accounts for the second transfer and so on
+    WithdrawBatch(WithdrawBatchArgs),
+
+    /// Verify signatures of the Merkle root once and store it in the SignedRoot account,
+    /// so withdrawals can reference it instead of carrying signatures.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[writable]` The new SignedRoot account
+    ///   2. `[writable,signer]` The fee payer
+    ///   3. `[]` System program
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` Instructions sysvar
+    SubmitRoot(SubmitRootArgs),
+
+    /// Switch replay protection of withdrawals from the Withdraw accounts to the WithdrawBitmap.
+    /// The bitmap mode can not be switched back.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[]` Instructions sysvar
+    SetWithdrawMode(SetWithdrawModeArgs),
+
+    /// Migrate the Withdraw account into the WithdrawBitmap and refund its rent to the payer.
+    /// Available only in the bitmap mode.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[writable]` The Withdraw account
+    ///   2. `[writable]` The WithdrawBitmap account
+    ///   3. `[writable]` The rent payer stored in the Withdraw account (receiver for the legacy accounts)
+    ///   4. `[writable,signer]` The fee payer
+    ///   5. `[]` System program
+    ///   6. `[]` Rent sysvar
+    ///   7. `[]` Instructions sysvar
+    CloseWithdraw(CloseWithdrawArgs),
+
+    /// Deposit compressed NFT: transfer the leaf to the bridge admin with Bubblegum
+    /// or burn the leaf of the wrapped asset.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[writable,signer]` The leaf owner
+    ///   2. `[]` The leaf delegate (owner if not delegated)
+    ///   3. `[]` The Bubblegum tree config
+    ///   4. `[writable]` The Merkle tree
+    ///   5. `[]` Bubblegum program
+    ///   6. `[]` Noop program
+    ///   7. `[]` Account compression program
+    ///   8. `[]` System program
+    ///   9. `[]` Rent sysvar
+    ///   10. `[]` Instructions sysvar
+    ///   11. `[]` The NetworkRegistry account
+    ///   12. `[]` The TokenPolicy account
+    ///   13. `[writable]` The WrappedAsset account of the token seed (only for the wrapped asset)
+    ///   14. `[writable]` Optional Deposit account of the current deposit nonce (recognized by the address)
+    ///
+    ///   15..N. `[]` Proof nodes of the leaf
+    DepositCNFT(DepositCNFTArgs),
+
+    /// Withdraw compressed NFT: transfer the leaf owned by the bridge admin
+    /// or mint the wrapped asset into the tree delegated to the bridge admin.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[]` The receiver (leaf owner)
+    ///   2. `[writable,signer]` The fee payer
+    ///   3. `[writable]` The Withdraw or WithdrawBitmap account
+    ///   4. `[writable]` The Bubblegum tree config
+    ///   5. `[writable]` The Merkle tree
+    ///   6. `[]` Bubblegum program
+    ///   7. `[]` Noop program
+    ///   8. `[]` Account compression program
+    ///   9. `[]` System program
+    ///   10. `[]` Rent sysvar
+    ///   11. `[writable]` The RateLimit account of the asset
+    ///   12. `[]` Instructions sysvar
+    ///   13. `[writable]` The WrappedAsset account of the token seed (only for the mint)
+    ///
+    ///   14..N. `[]` Proof nodes of the leaf (for the transfer)
+    WithdrawCNFT(WithdrawCNFTArgs),
+
+    /// Update metadata of the wrapped FT or NFT mint with the signed data
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The BridgeAdmin account
+    ///   1. `[]` The wrapped token mint account
+    ///   2. `[writable]` The token metadata account
+    ///   3. `[writable]` The MetadataNonce account of the mint
+    ///   4. `[writable,signer]` The fee payer
+    ///   5. `[]` System program
+    ///   6. `[]` Rent sysvar
+    ///   7. `[]` Metadata program
+    ///   8. `[]` Instructions sysvar
+    UpdateWrappedMetadata(UpdateWrappedMetadataArgs),
+
+    /// Move SOL above the rent-exempt reserve of the BridgeAdmin account into the SOL vault.
+    /// Available after the BridgeAdmin migration.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[writable]` The SOL vault account
+    ///   2. `[]` Instructions sysvar
+    MigrateSolVault(MigrateSolVaultArgs),
+
+    /// Migrate the BridgeAdmin account with the legacy layout (single ECDSA public key)
+    /// into the current one with the Secp256k1 signer set of this key and threshold 1.
+    /// Should be the first instruction after the program upgrade.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The BridgeAdmin account
+    ///   1. `[writable,signer]` The fee payer, pays the rent for the new size
+    ///   2. `[]` System program
+    MigrateAdmin(MigrateAdminArgs),
+}
+
+
+impl InstructionValidation for InitializeAdminArgs {
+    fn validate(&self) -> ProgramResult {
+        self.signers.validate()
+    }
+}
+
+impl InstructionValidation for TransferOwnershipArgs {
+    fn validate(&self) -> ProgramResult {
+        self.new_signers.validate()
+    }
+}
+
+impl InstructionValidation for DepositNativeArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.receiver_address.as_bytes().len() > MAX_ADDRESS_SIZE ||
+            self.network_to.as_bytes().len() > MAX_NETWORKS_SIZE || self.amount <= 0 {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        validate_bundle(&self.bundle_data, &self.bundle_seed)
+    }
+}
+
+impl InstructionValidation for DepositFTArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.receiver_address.as_bytes().len() > MAX_ADDRESS_SIZE ||
+            self.network_to.as_bytes().len() > MAX_NETWORKS_SIZE || self.amount <= 0 {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        validate_bundle(&self.bundle_data, &self.bundle_seed)
+    }
+}
+
+impl InstructionValidation for DepositNFTArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.receiver_address.as_bytes().len() > MAX_ADDRESS_SIZE || self.network_to.as_bytes().len() > MAX_NETWORKS_SIZE {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        validate_bundle(&self.bundle_data, &self.bundle_seed)
+    }
+}
+
+impl InstructionValidation for DepositCNFTArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.receiver_address.len() > MAX_ADDRESS_SIZE || self.network_to.len() > MAX_NETWORKS_SIZE {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        validate_bundle(&self.bundle_data, &self.bundle_seed)
+    }
+}
+
+impl InstructionValidation for WithdrawCNFTArgs {
+    fn validate(&self) -> ProgramResult {
+        match (&self.leaf, self.token_seed, &self.signed_meta) {
+            (Some(_), None, None) => Ok(()),
+            (None, Some(_), Some(signed_meta)) if signed_meta.collection.is_some() => Err(LibError::WrongCollection.into()),
+            (None, Some(_), Some(signed_meta)) => signed_meta.validate(),
+            (None, Some(_), None) => Err(LibError::NoTokenMeta.into()),
+            _ => Err(LibError::WrongTokenSeed.into()),
+        }
+    }
+}
+
+// Bundle data and seed should be provided together
+fn validate_bundle(bundle_data: &Option<Vec<u8>>, bundle_seed: &Option<[u8; 32]>) -> ProgramResult {
+    match (bundle_data, bundle_seed) {
+        (None, None) => Ok(()),
+        (Some(data), Some(_)) => {
+            if data.is_empty() || data.len() > MAX_BUNDLE_SIZE {
+                return Err(LibError::WrongArgsSize.into());
+            }
+
+            Ok(())
+        }
+        _ => Err(LibError::WrongBundle.into()),
     }
 }
 
@@ -281,177 +973,1176 @@ impl InstructionValidation for WithdrawArgs {
             return Err(LibError::WrongArgsSize.into());
         }
 
+        validate_relayer_fee(self.relayer_fee, self.amount)
+    }
+}
+
+fn validate_relayer_fee(relayer_fee: Option<u64>, amount: u64) -> ProgramResult {
+    if let Some(fee) = relayer_fee {
+        if fee >= amount {
+            return Err(LibError::WrongRelayerFee.into());
+        }
+    }
+
+    Ok(())
+}
+
+impl InstructionValidation for WithdrawTransfer {
+    fn validate(&self) -> ProgramResult {
+        if self.token_type == TokenType::Message || self.token_type == TokenType::CNFT {
+            return Err(LibError::WrongTokenType.into());
+        }
+
+        if self.token_type != TokenType::NFT && self.amount == 0 {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        if self.token_type == TokenType::NFT && self.relayer_fee.is_some() {
+            return Err(LibError::WrongRelayerFee.into());
+        }
+
+        if self.token_type != TokenType::Native && self.wrapped_sol {
+            return Err(LibError::WrongTokenType.into());
+        }
+
+        validate_relayer_fee(self.relayer_fee, self.amount)
+    }
+}
+
+impl InstructionValidation for WithdrawBatchArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.transfers.is_empty() || self.transfers.len() > MAX_BATCH_SIZE {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        for transfer in &self.transfers {
+            transfer.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl InstructionValidation for WithdrawAndCallArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.token_type == TokenType::NFT && self.withdraw.relayer_fee.is_some() {
+            return Err(LibError::WrongRelayerFee.into());
+        }
+
+        if self.token_type != TokenType::Native && self.withdraw.wrapped_sol {
+            return Err(LibError::WrongTokenType.into());
+        }
+
+        self.withdraw.validate()?;
+        self.call.validate()
+    }
+}
+
+impl InstructionValidation for TargetCall {
+    fn validate(&self) -> ProgramResult {
+        if self.payload.len() > MAX_PAYLOAD_SIZE || self.accounts.len() > MAX_CALL_ACCOUNTS_COUNT {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl InstructionValidation for SendMessageArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.target_address.len() > MAX_ADDRESS_SIZE ||
+            self.network_to.len() > MAX_NETWORKS_SIZE ||
+            self.payload.is_empty() || self.payload.len() > MAX_PAYLOAD_SIZE {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl InstructionValidation for ReceiveMessageArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.sender.len() > MAX_ADDRESS_SIZE || self.payload.len() > MAX_PAYLOAD_SIZE {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl InstructionValidation for NetworkArgs {
+    fn validate(&self) -> ProgramResult {
+        self.network.validate()
+    }
+}
+
+impl InstructionValidation for SetDepositLimitArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.min_amount > self.max_amount {
+            return Err(LibError::WrongDepositAmount.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl InstructionValidation for SetTokenPolicyArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.mints.len() > MAX_POLICY_TOKENS_COUNT {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
         Ok(())
     }
 }
 
-impl InstructionValidation for MintCollectionArgs {
-    fn validate(&self) -> ProgramResult {
-        self.data.validate()
+impl InstructionValidation for MintCollectionArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.data.collection.is_some() {
+            return Err(LibError::WrongCollection.into());
+        }
+
+        self.data.validate()
+    }
+}
+
+impl InstructionValidation for SignedMetadata {
+    fn validate(&self) -> ProgramResult {
+        if self.name.as_bytes().len() > mpl_token_metadata::MAX_NAME_LENGTH ||
+            self.symbol.as_bytes().len() > mpl_token_metadata::MAX_SYMBOL_LENGTH ||
+            self.uri.as_bytes().len() > mpl_token_metadata::MAX_URI_LENGTH {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        match &self.royalties {
+            Some(royalties) => validate_royalties(royalties.seller_fee_basis_points, &royalties.creators),
+            None => Ok(()),
+        }
+    }
+}
+
+impl InstructionValidation for UpdateWrappedMetadataArgs {
+    fn validate(&self) -> ProgramResult {
+        if self.name.len() > mpl_token_metadata::MAX_NAME_LENGTH ||
+            self.symbol.len() > mpl_token_metadata::MAX_SYMBOL_LENGTH ||
+            self.uri.len() > mpl_token_metadata::MAX_URI_LENGTH {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        validate_royalties(self.seller_fee_basis_points, &self.creators)
+    }
+}
+
+// Metaplex requires creator shares to add up to 100
+fn validate_royalties(seller_fee_basis_points: u16, creators: &[SignedCreator]) -> ProgramResult {
+    if seller_fee_basis_points > 10000 || creators.len() > mpl_token_metadata::MAX_CREATOR_LIMIT {
+        return Err(LibError::WrongArgsSize.into());
+    }
+
+    if !creators.is_empty() && creators.iter().map(|creator| creator.share as u32).sum::<u32>() != 100 {
+        return Err(LibError::WrongArgsSize.into());
+    }
+
+    Ok(())
+}
+
+pub fn get_deposit_address(program_id: &Pubkey, bridge_admin: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::DEPOSIT_PDA_SEED.as_bytes(), bridge_admin.as_ref(), nonce.to_be_bytes().as_slice()],
+        program_id,
+    )
+}
+
+pub fn get_call_authority_address(program_id: &Pubkey, bridge_admin: &Pubkey, target_program: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::CALL_AUTHORITY_PDA_SEED.as_bytes(), bridge_admin.as_ref(), target_program.as_ref()],
+        program_id,
+    )
+}
+
+pub fn get_message_address(program_id: &Pubkey, bridge_admin: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::MESSAGE_PDA_SEED.as_bytes(), bridge_admin.as_ref(), nonce.to_be_bytes().as_slice()],
+        program_id,
+    )
+}
+
+pub fn get_network_registry_address(program_id: &Pubkey, bridge_admin: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::NETWORK_REGISTRY_PDA_SEED.as_bytes(), bridge_admin.as_ref()],
+        program_id,
+    )
+}
+
+// Native token uses default key instead of mint
+pub fn get_rate_limit_address(program_id: &Pubkey, bridge_admin: &Pubkey, mint: Option<Pubkey>) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::RATE_LIMIT_PDA_SEED.as_bytes(), bridge_admin.as_ref(), mint.unwrap_or_default().as_ref()],
+        program_id,
+    )
+}
+
+// Native token uses default key instead of mint
+pub fn get_deposit_limit_address(program_id: &Pubkey, bridge_admin: &Pubkey, mint: Option<Pubkey>) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::DEPOSIT_LIMIT_PDA_SEED.as_bytes(), bridge_admin.as_ref(), mint.unwrap_or_default().as_ref()],
+        program_id,
+    )
+}
+
+pub fn get_token_policy_address(program_id: &Pubkey, bridge_admin: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::TOKEN_POLICY_PDA_SEED.as_bytes(), bridge_admin.as_ref()],
+        program_id,
+    )
+}
+
+pub fn get_withdraw_bitmap_address(program_id: &Pubkey, bridge_admin: &Pubkey, bitmap_index: u64) -> (Pubkey, u8) {
+    let shard = bitmap_index / WITHDRAW_BITMAP_BITS;
+    Pubkey::find_program_address(
+        &[crate::WITHDRAW_BITMAP_PDA_SEED.as_bytes(), bridge_admin.as_ref(), shard.to_be_bytes().as_slice()],
+        program_id,
+    )
+}
+
+pub fn get_signed_root_address(program_id: &Pubkey, bridge_admin: &Pubkey, root: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::SIGNED_ROOT_PDA_SEED.as_bytes(), bridge_admin.as_ref(), root.as_slice()],
+        program_id,
+    )
+}
+
+// System account holding the bridged SOL, separate from the BridgeAdmin data account
+pub fn get_sol_vault_address(program_id: &Pubkey, bridge_admin: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::SOL_VAULT_PDA_SEED.as_bytes(), bridge_admin.as_ref()],
+        program_id,
+    )
+}
+
+// Asset id of the leaf minted for the wrapped compressed NFT
+pub fn get_wrapped_asset_address(program_id: &Pubkey, bridge_admin: &Pubkey, token_seed: [u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::WRAPPED_ASSET_PDA_SEED.as_bytes(), bridge_admin.as_ref(), token_seed.as_slice()],
+        program_id,
+    )
+}
+
+pub fn get_metadata_nonce_address(program_id: &Pubkey, bridge_admin: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::METADATA_NONCE_PDA_SEED.as_bytes(), bridge_admin.as_ref(), mint.as_ref()],
+        program_id,
+    )
+}
+
+// Accounts of the Metaplex transfer passed after the token metadata for programmable NFTs.
+// Without the rule set its accounts are replaced with the metadata program as Metaplex expects.
+pub fn get_programmable_accounts(
+    mint: &Pubkey,
+    owner_associated: &Pubkey,
+    bridge_associated: &Pubkey,
+    authorization_rules: Option<Pubkey>,
+) -> Vec<AccountMeta> {
+    let (rules_program, rules) = match authorization_rules {
+        Some(rules) => (crate::TOKEN_AUTH_RULES_PROGRAM_ID, rules),
+        None => (mpl_token_metadata::ID, mpl_token_metadata::ID),
+    };
+
+    vec![
+        AccountMeta::new_readonly(MasterEdition::find_pda(mint).0, false),
+        AccountMeta::new(TokenRecord::find_pda(mint, owner_associated).0, false),
+        AccountMeta::new(TokenRecord::find_pda(mint, bridge_associated).0, false),
+        AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(rules_program, false),
+        AccountMeta::new_readonly(rules, false),
+    ]
+}
+
+// Compressed NFT is identified by the Bubblegum asset id, wrapped one - by the token seed as wrapped mints
+// in both directions (the id of its current leaf is stored in the WrappedAsset account)
+pub fn get_compressed_asset_id(program_id: &Pubkey, merkle_tree: &Pubkey, leaf: &Option<CompressedLeaf>, token_seed: Option<[u8; 32]>) -> Pubkey {
+    match (leaf, token_seed) {
+        (Some(leaf), _) => mpl_bubblegum::utils::get_asset_id(merkle_tree, leaf.nonce),
+        (None, Some(token_seed)) => Pubkey::find_program_address(&[token_seed.as_slice()], program_id).0,
+        (None, None) => Pubkey::default(),
+    }
+}
+
+pub fn initialize_admin(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    fee_payer: Pubkey,
+    commission_program: Pubkey,
+    signers: SignerScheme,
+    seeds: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new(fee_payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(get_sol_vault_address(&program_id, &bridge_admin).0, false),
+        ],
+        data: BridgeInstruction::InitializeAdmin(InitializeAdminArgs {
+            signers,
+            seeds,
+            commission_program,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn transfer_ownership(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    signatures: Vec<EcdsaSignature>,
+    new_signers: SignerScheme,
+    seeds: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::TransferOwnership(TransferOwnershipArgs {
+            signatures,
+            new_signers,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn deposit_native(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    owner: Pubkey,
+    seeds: [u8; 32],
+    network_to: String,
+    amount: u64,
+    receiver_address: String,
+    bundle_data: Option<Vec<u8>>,
+    bundle_seed: Option<[u8; 32]>,
+    wrapped_sol: bool,
+    deposit: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(bridge_admin, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(get_network_registry_address(&program_id, &bridge_admin).0, false),
+        AccountMeta::new_readonly(get_deposit_limit_address(&program_id, &bridge_admin, None).0, false),
+        AccountMeta::new(get_sol_vault_address(&program_id, &bridge_admin).0, false),
+    ];
+
+    if wrapped_sol {
+        accounts.push(AccountMeta::new(get_associated_token_address_with_program_id(&owner, &spl_token::native_mint::id(), &spl_token::id()), false));
+        accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+    }
+
+    if let Some(deposit) = deposit {
+        accounts.push(AccountMeta::new(deposit, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: BridgeInstruction::DepositNative(DepositNativeArgs {
+            amount,
+            network_to,
+            receiver_address,
+            seeds,
+            bundle_data,
+            bundle_seed,
+            wrapped_sol,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn deposit_ft(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    owner: Pubkey,
+    seeds: [u8; 32],
+    network_to: String,
+    receiver_address: String,
+    amount: u64,
+    token_seed: Option<[u8; 32]>,
+    bundle_data: Option<Vec<u8>>,
+    bundle_seed: Option<[u8; 32]>,
+    deposit: Option<Pubkey>,
+) -> Instruction {
+    let owner_associated = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let bridge_associated = get_associated_token_address_with_program_id(&bridge_admin, &mint, &token_program);
+
+    let mut accounts = vec![
+        AccountMeta::new(bridge_admin, false),
+        AccountMeta::new(mint, false),
+        AccountMeta::new(owner_associated, false),
+        AccountMeta::new(bridge_associated, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(get_network_registry_address(&program_id, &bridge_admin).0, false),
+        AccountMeta::new_readonly(get_deposit_limit_address(&program_id, &bridge_admin, Some(mint)).0, false),
+        AccountMeta::new_readonly(get_token_policy_address(&program_id, &bridge_admin).0, false),
+    ];
+
+    if let Some(deposit) = deposit {
+        accounts.push(AccountMeta::new(deposit, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: BridgeInstruction::DepositFT(DepositFTArgs {
+            amount,
+            network_to,
+            receiver_address,
+            seeds,
+            token_seed,
+            bundle_data,
+            bundle_seed,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn deposit_nft(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    owner: Pubkey,
+    seeds: [u8; 32],
+    network_to: String,
+    receiver_address: String,
+    token_seed: Option<[u8; 32]>,
+    bundle_data: Option<Vec<u8>>,
+    bundle_seed: Option<[u8; 32]>,
+    programmable: bool,
+    authorization_rules: Option<Pubkey>,
+    deposit: Option<Pubkey>,
+) -> Instruction {
+    let owner_associated = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let bridge_associated = get_associated_token_address_with_program_id(&bridge_admin, &mint, &token_program);
+
+    let mut accounts = vec![
+        AccountMeta::new(bridge_admin, false),
+        AccountMeta::new(mint, false),
+        AccountMeta::new(owner_associated, false),
+        AccountMeta::new(bridge_associated, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(get_network_registry_address(&program_id, &bridge_admin).0, false),
+        AccountMeta::new_readonly(get_token_policy_address(&program_id, &bridge_admin).0, false),
+        AccountMeta::new(Metadata::find_pda(&mint).0, false),
+    ];
+
+    if programmable {
+        accounts.append(&mut get_programmable_accounts(&mint, &owner_associated, &bridge_associated, authorization_rules));
+    }
+
+    if let Some(deposit) = deposit {
+        accounts.push(AccountMeta::new(deposit, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: BridgeInstruction::DepositNFT(DepositNFTArgs {
+            network_to,
+            receiver_address,
+            seeds,
+            token_seed,
+            bundle_data,
+            bundle_seed,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn withdraw_native(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    owner: Pubkey,
+    fee_payer: Pubkey,
+    withdraw: Pubkey,
+    seeds: [u8; 32],
+    origin: [u8; 32],
+    amount: u64,
+    proof: RootProof,
+    path: Vec<[u8; 32]>,
+    token_seed: Option<[u8; 32]>,
+    signed_meta: Option<SignedMetadata>,
+    relayer_fee: Option<u64>,
+    bitmap_index: Option<u64>,
+    wrapped_sol: bool,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(bridge_admin, false),
+        AccountMeta::new(owner, false),
+        AccountMeta::new(fee_payer, true),
+        AccountMeta::new(withdraw, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(get_rate_limit_address(&program_id, &bridge_admin, None).0, false),
+        AccountMeta::new(get_sol_vault_address(&program_id, &bridge_admin).0, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    if wrapped_sol {
+        accounts.push(AccountMeta::new(get_associated_token_address_with_program_id(&owner, &spl_token::native_mint::id(), &spl_token::id()), false));
+        accounts.push(AccountMeta::new_readonly(spl_token::native_mint::id(), false));
+        accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+        accounts.push(AccountMeta::new_readonly(spl_associated_token_account::id(), false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: BridgeInstruction::WithdrawNative(WithdrawArgs {
+            origin,
+            amount,
+            proof,
+            path,
+            seeds,
+            token_seed,
+            signed_meta,
+            relayer_fee,
+            bitmap_index,
+            wrapped_sol,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn withdraw_ft(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    owner: Pubkey,
+    fee_payer: Pubkey,
+    withdraw: Pubkey,
+    seeds: [u8; 32],
+    origin: [u8; 32],
+    amount: u64,
+    proof: RootProof,
+    path: Vec<[u8; 32]>,
+    token_seed: Option<[u8; 32]>,
+    signed_meta: Option<SignedMetadata>,
+    relayer_fee: Option<u64>,
+    bitmap_index: Option<u64>,
+) -> Instruction {
+    let fee_payer_associated = get_associated_token_address_with_program_id(&fee_payer, &mint, &token_program);
+    let owner_associated = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let bridge_associated = get_associated_token_address_with_program_id(&bridge_admin, &mint, &token_program);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(bridge_admin, false),
+            AccountMeta::new(mint, false),
+            AccountMeta::new(Metadata::find_pda(&mint).0, false),
+            AccountMeta::new(owner, false),
+            AccountMeta::new(fee_payer, true),
+            AccountMeta::new(fee_payer_associated, false),
+            AccountMeta::new(owner_associated, false),
+            AccountMeta::new(bridge_associated, false),
+            AccountMeta::new(withdraw, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new(get_rate_limit_address(&program_id, &bridge_admin, Some(mint)).0, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::WithdrawFT(WithdrawArgs {
+            origin,
+            amount,
+            proof,
+            path,
+            seeds,
+            token_seed,
+            signed_meta,
+            relayer_fee,
+            bitmap_index,
+            wrapped_sol: false,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn withdraw_nft(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    metadata: Pubkey,
+    owner: Pubkey,
+    fee_payer: Pubkey,
+    withdraw: Pubkey,
+    seeds: [u8; 32],
+    origin: [u8; 32],
+    amount: u64,
+    proof: RootProof,
+    path: Vec<[u8; 32]>,
+    token_seed: Option<[u8; 32]>,
+    signed_meta: Option<SignedMetadata>,
+    bitmap_index: Option<u64>,
+    programmable: bool,
+    authorization_rules: Option<Pubkey>,
+) -> Instruction {
+    let owner_associated = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let bridge_associated = get_associated_token_address_with_program_id(&bridge_admin, &mint, &token_program);
+    let collection = match token_seed {
+        Some(_) => signed_meta.as_ref().and_then(|meta| meta.collection),
+        None => None,
+    };
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(bridge_admin, false),
+        AccountMeta::new(mint, false),
+        AccountMeta::new(metadata, false),
+        AccountMeta::new(owner, false),
+        AccountMeta::new(fee_payer, true),
+        AccountMeta::new(owner_associated, false),
+        AccountMeta::new(bridge_associated, false),
+        AccountMeta::new(withdraw, false),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new(get_rate_limit_address(&program_id, &bridge_admin, Some(mint)).0, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    if token_seed.is_some() {
+        accounts.push(AccountMeta::new(MasterEdition::find_pda(&mint).0, false));
+    }
+
+    if let Some(collection) = collection {
+        accounts.push(AccountMeta::new_readonly(collection, false));
+        accounts.push(AccountMeta::new_readonly(MasterEdition::find_pda(&collection).0, false));
+    }
+
+    if programmable {
+        accounts.append(&mut get_programmable_accounts(&mint, &owner_associated, &bridge_associated, authorization_rules));
+    }
+
+    // Collection metadata of the wrapped token, other tokens from collections should append it
+    if let Some(collection) = collection {
+        accounts.push(AccountMeta::new(Metadata::find_pda(&collection).0, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: BridgeInstruction::WithdrawNFT(WithdrawArgs {
+            origin,
+            amount,
+            proof,
+            path,
+            seeds,
+            token_seed,
+            signed_meta,
+            relayer_fee: None,
+            bitmap_index,
+            wrapped_sol: false,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+// Wraps instruction created by `withdraw_native`, `withdraw_ft` or `withdraw_nft` into the WithdrawAndCall
+pub fn withdraw_and_call(
+    withdraw: Instruction,
+    target_program: Pubkey,
+    payload: Vec<u8>,
+    call_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let (token_type, args) = match BridgeInstruction::try_from_slice(withdraw.data.as_slice()).unwrap() {
+        BridgeInstruction::WithdrawNative(args) => (TokenType::Native, args),
+        BridgeInstruction::WithdrawFT(args) => (TokenType::FT, args),
+        BridgeInstruction::WithdrawNFT(args) => (TokenType::NFT, args),
+        _ => panic!("not a withdraw instruction"),
+    };
+
+    let (call_authority, _) = get_call_authority_address(&withdraw.program_id, &withdraw.accounts[0].pubkey, &target_program);
+
+    let target_accounts = call_accounts.iter()
+        .map(|meta| TargetAccount { pubkey: meta.pubkey, is_writable: meta.is_writable })
+        .collect();
+
+    let mut accounts = withdraw.accounts;
+    accounts.push(AccountMeta::new_readonly(target_program, false));
+    accounts.push(AccountMeta::new_readonly(call_authority, false));
+    accounts.extend(call_accounts);
+
+    Instruction {
+        program_id: withdraw.program_id,
+        accounts,
+        data: BridgeInstruction::WithdrawAndCall(WithdrawAndCallArgs {
+            token_type,
+            withdraw: args,
+            call: TargetCall {
+                program: target_program,
+                accounts: target_accounts,
+                payload,
+            },
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn send_message(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    sender: Pubkey,
+    seeds: [u8; 32],
+    network_to: String,
+    target_address: String,
+    payload: Vec<u8>,
+    message: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(bridge_admin, false),
+        AccountMeta::new(sender, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(get_network_registry_address(&program_id, &bridge_admin).0, false),
+    ];
+
+    if let Some(message) = message {
+        accounts.push(AccountMeta::new(message, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: BridgeInstruction::SendMessage(SendMessageArgs {
+            network_to,
+            target_address,
+            payload,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn receive_message(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    receiver: Pubkey,
+    payer: Pubkey,
+    receipt: Pubkey,
+    seeds: [u8; 32],
+    origin: [u8; 32],
+    sender: String,
+    payload: Vec<u8>,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(bridge_admin, false),
+            AccountMeta::new_readonly(receiver, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::ReceiveMessage(ReceiveMessageArgs {
+            origin,
+            sender,
+            payload,
+            signatures,
+            path,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn initialize_network_registry(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    fee_payer: Pubkey,
+    seeds: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new(get_network_registry_address(&program_id, &bridge_admin).0, false),
+            AccountMeta::new(fee_payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::InitializeNetworkRegistry(InitializeNetworkRegistryArgs {
+            signatures,
+            path,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn add_network(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    seeds: [u8; 32],
+    network: Network,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(bridge_admin, false),
+            AccountMeta::new(get_network_registry_address(&program_id, &bridge_admin).0, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::AddNetwork(NetworkArgs {
+            signatures,
+            path,
+            network,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn remove_network(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    seeds: [u8; 32],
+    network: Network,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(bridge_admin, false),
+            AccountMeta::new(get_network_registry_address(&program_id, &bridge_admin).0, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::RemoveNetwork(NetworkArgs {
+            signatures,
+            path,
+            network,
+            seeds,
+        }).try_to_vec().unwrap(),
     }
 }
 
-impl InstructionValidation for SignedMetadata {
-    fn validate(&self) -> ProgramResult {
-        if self.name.as_bytes().len() > mpl_token_metadata::MAX_NAME_LENGTH ||
-            self.symbol.as_bytes().len() > mpl_token_metadata::MAX_SYMBOL_LENGTH ||
-            self.uri.as_bytes().len() > mpl_token_metadata::MAX_URI_LENGTH {
-            return Err(LibError::WrongArgsSize.into());
-        }
-
-        Ok(())
+pub fn set_pause(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    seeds: [u8; 32],
+    pause: PauseState,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::SetPause(SetPauseArgs {
+            signatures,
+            path,
+            pause,
+            seeds,
+        }).try_to_vec().unwrap(),
     }
 }
 
-pub fn initialize_admin(
+pub fn set_rate_limit(
     program_id: Pubkey,
     bridge_admin: Pubkey,
     fee_payer: Pubkey,
-    commission_program: Pubkey,
-    public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
     seeds: [u8; 32],
+    mint: Option<Pubkey>,
+    capacity: u64,
+    refill_per_second: u64,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
 ) -> Instruction {
     Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new_readonly(bridge_admin, false),
+            AccountMeta::new(get_rate_limit_address(&program_id, &bridge_admin, mint).0, false),
             AccountMeta::new(fee_payer, true),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
         ],
-        data: BridgeInstruction::InitializeAdmin(InitializeAdminArgs {
-            public_key,
+        data: BridgeInstruction::SetRateLimit(SetRateLimitArgs {
+            signatures,
+            path,
+            mint,
+            capacity,
+            refill_per_second,
             seeds,
-            commission_program,
         }).try_to_vec().unwrap(),
     }
 }
 
-pub fn transfer_ownership(
+pub fn set_deposit_limit(
     program_id: Pubkey,
     bridge_admin: Pubkey,
-    signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    new_public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    fee_payer: Pubkey,
     seeds: [u8; 32],
+    mint: Option<Pubkey>,
+    min_amount: u64,
+    max_amount: u64,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
 ) -> Instruction {
     Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new_readonly(bridge_admin, false),
+            AccountMeta::new(get_deposit_limit_address(&program_id, &bridge_admin, mint).0, false),
+            AccountMeta::new(fee_payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
         ],
-        data: BridgeInstruction::TransferOwnership(TransferOwnershipArgs {
-            signature,
-            new_public_key,
+        data: BridgeInstruction::SetDepositLimit(SetDepositLimitArgs {
+            signatures,
+            path,
+            mint,
+            min_amount,
+            max_amount,
             seeds,
-            recovery_id,
         }).try_to_vec().unwrap(),
     }
 }
 
-pub fn deposit_native(
+pub fn set_token_policy(
     program_id: Pubkey,
     bridge_admin: Pubkey,
-    owner: Pubkey,
+    fee_payer: Pubkey,
     seeds: [u8; 32],
-    network_to: String,
-    amount: u64,
-    receiver_address: String,
-    bundle_data: Option<Vec<u8>>,
-    bundle_seed: Option<[u8; 32]>,
+    mode: TokenPolicyMode,
+    mints: Vec<Pubkey>,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
 ) -> Instruction {
     Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new(bridge_admin, false),
-            AccountMeta::new(owner, true),
+            AccountMeta::new_readonly(bridge_admin, false),
+            AccountMeta::new(get_token_policy_address(&program_id, &bridge_admin).0, false),
+            AccountMeta::new(fee_payer, true),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
         ],
-        data: BridgeInstruction::DepositNative(DepositNativeArgs {
-            amount,
-            network_to,
-            receiver_address,
+        data: BridgeInstruction::SetTokenPolicy(SetTokenPolicyArgs {
+            signatures,
+            path,
+            mode,
+            mints,
             seeds,
-            bundle_data,
-            bundle_seed,
         }).try_to_vec().unwrap(),
     }
 }
 
-pub fn deposit_ft(
+// Joins instructions created by `withdraw_native`, `withdraw_ft` or `withdraw_nft` into the WithdrawBatch.
+// All withdrawals should be signed under the same root, proof is taken from the first one.
+pub fn withdraw_batch(withdrawals: Vec<Instruction>) -> Instruction {
+    let program_id = withdrawals[0].program_id;
+    let mut accounts = Vec::new();
+    let mut transfers = Vec::new();
+    let mut proof = RootProof::Signatures(Vec::new());
+    let mut seeds = [0; 32];
+
+    for withdraw in withdrawals {
+        let (token_type, args) = match BridgeInstruction::try_from_slice(withdraw.data.as_slice()).unwrap() {
+            BridgeInstruction::WithdrawNative(args) => (TokenType::Native, args),
+            BridgeInstruction::WithdrawFT(args) => (TokenType::FT, args),
+            BridgeInstruction::WithdrawNFT(args) => (TokenType::NFT, args),
+            _ => panic!("not a withdraw instruction"),
+        };
+
+        if transfers.is_empty() {
+            proof = args.proof;
+            seeds = args.seeds;
+        }
+
+        accounts.extend(withdraw.accounts);
+        transfers.push(WithdrawTransfer {
+            token_type,
+            origin: args.origin,
+            amount: args.amount,
+            path: args.path,
+            token_seed: args.token_seed,
+            signed_meta: args.signed_meta,
+            relayer_fee: args.relayer_fee,
+            bitmap_index: args.bitmap_index,
+            wrapped_sol: args.wrapped_sol,
+        });
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: BridgeInstruction::WithdrawBatch(WithdrawBatchArgs {
+            proof,
+            transfers,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+
+pub fn submit_root(
     program_id: Pubkey,
     bridge_admin: Pubkey,
-    mint: Pubkey,
-    owner: Pubkey,
+    fee_payer: Pubkey,
     seeds: [u8; 32],
-    network_to: String,
-    receiver_address: String,
-    amount: u64,
-    token_seed: Option<[u8; 32]>,
-    bundle_data: Option<Vec<u8>>,
-    bundle_seed: Option<[u8; 32]>,
+    root: [u8; 32],
+    signatures: Vec<EcdsaSignature>,
 ) -> Instruction {
-    let owner_associated = get_associated_token_address(&owner, &mint);
-    let bridge_associated = get_associated_token_address(&bridge_admin, &mint);
-
     Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new_readonly(bridge_admin, false),
-            AccountMeta::new(mint, false),
-            AccountMeta::new(owner_associated, false),
-            AccountMeta::new(bridge_associated, false),
-            AccountMeta::new(owner, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(get_signed_root_address(&program_id, &bridge_admin, &root).0, false),
+            AccountMeta::new(fee_payer, true),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
         ],
-        data: BridgeInstruction::DepositFT(DepositFTArgs {
-            amount,
-            network_to,
-            receiver_address,
+        data: BridgeInstruction::SubmitRoot(SubmitRootArgs {
+            root,
+            signatures,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+pub fn set_withdraw_mode(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    seeds: [u8; 32],
+    mode: WithdrawMode,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::SetWithdrawMode(SetWithdrawModeArgs {
+            signatures,
+            path,
+            mode,
             seeds,
-            token_seed,
-            bundle_data,
-            bundle_seed,
         }).try_to_vec().unwrap(),
     }
 }
 
-pub fn deposit_nft(
+pub fn close_withdraw(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    rent_payer: Pubkey,
+    fee_payer: Pubkey,
+    seeds: [u8; 32],
+    origin: [u8; 32],
+    bitmap_index: u64,
+    signatures: Vec<EcdsaSignature>,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new(Pubkey::find_program_address(&[origin.as_slice()], &program_id).0, false),
+            AccountMeta::new(get_withdraw_bitmap_address(&program_id, &bridge_admin, bitmap_index).0, false),
+            AccountMeta::new(rent_payer, false),
+            AccountMeta::new(fee_payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::CloseWithdraw(CloseWithdrawArgs {
+            signatures,
+            path,
+            origin,
+            bitmap_index,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
+pub fn deposit_cnft(
     program_id: Pubkey,
     bridge_admin: Pubkey,
-    mint: Pubkey,
     owner: Pubkey,
+    delegate: Pubkey,
+    merkle_tree: Pubkey,
     seeds: [u8; 32],
     network_to: String,
     receiver_address: String,
+    leaf: CompressedLeaf,
+    leaf_proof: Vec<Pubkey>,
     token_seed: Option<[u8; 32]>,
     bundle_data: Option<Vec<u8>>,
     bundle_seed: Option<[u8; 32]>,
+    deposit: Option<Pubkey>,
 ) -> Instruction {
-    let owner_associated = get_associated_token_address(&owner, &mint);
-    let bridge_associated = get_associated_token_address(&bridge_admin, &mint);
+    let mut accounts = vec![
+        AccountMeta::new(bridge_admin, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new_readonly(delegate, false),
+        AccountMeta::new_readonly(TreeConfig::find_pda(&merkle_tree).0, false),
+        AccountMeta::new(merkle_tree, false),
+        AccountMeta::new_readonly(mpl_bubblegum::ID, false),
+        AccountMeta::new_readonly(mpl_bubblegum::programs::SPL_NOOP_ID, false),
+        AccountMeta::new_readonly(mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(get_network_registry_address(&program_id, &bridge_admin).0, false),
+        AccountMeta::new_readonly(get_token_policy_address(&program_id, &bridge_admin).0, false),
+    ];
+
+    if let Some(token_seed) = token_seed {
+        accounts.push(AccountMeta::new(get_wrapped_asset_address(&program_id, &bridge_admin, token_seed).0, false));
+    }
+
+    if let Some(deposit) = deposit {
+        accounts.push(AccountMeta::new(deposit, false));
+    }
+
+    accounts.extend(leaf_proof.into_iter().map(|node| AccountMeta::new_readonly(node, false)));
 
     Instruction {
         program_id,
-        accounts: vec![
-            AccountMeta::new_readonly(bridge_admin, false),
-            AccountMeta::new(mint, false),
-            AccountMeta::new(owner_associated, false),
-            AccountMeta::new(bridge_associated, false),
-            AccountMeta::new(owner, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-            AccountMeta::new_readonly(solana_program::system_program::id(), false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
-        ],
-        data: BridgeInstruction::DepositNFT(DepositNFTArgs {
+        accounts,
+        data: BridgeInstruction::DepositCNFT(DepositCNFTArgs {
             network_to,
             receiver_address,
             seeds,
+            leaf,
             token_seed,
             bundle_data,
             bundle_seed,
@@ -459,132 +2150,179 @@ pub fn deposit_nft(
     }
 }
 
-pub fn withdraw_native(
+pub fn withdraw_cnft(
     program_id: Pubkey,
     bridge_admin: Pubkey,
     owner: Pubkey,
+    fee_payer: Pubkey,
     withdraw: Pubkey,
+    merkle_tree: Pubkey,
     seeds: [u8; 32],
     origin: [u8; 32],
-    amount: u64,
-    signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    proof: RootProof,
     path: Vec<[u8; 32]>,
+    leaf: Option<CompressedLeaf>,
+    leaf_proof: Vec<Pubkey>,
     token_seed: Option<[u8; 32]>,
     signed_meta: Option<SignedMetadata>,
+    bitmap_index: Option<u64>,
 ) -> Instruction {
+    let asset_id = get_compressed_asset_id(&program_id, &merkle_tree, &leaf, token_seed);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(bridge_admin, false),
+        AccountMeta::new_readonly(owner, false),
+        AccountMeta::new(fee_payer, true),
+        AccountMeta::new(withdraw, false),
+        AccountMeta::new(TreeConfig::find_pda(&merkle_tree).0, false),
+        AccountMeta::new(merkle_tree, false),
+        AccountMeta::new_readonly(mpl_bubblegum::ID, false),
+        AccountMeta::new_readonly(mpl_bubblegum::programs::SPL_NOOP_ID, false),
+        AccountMeta::new_readonly(mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(get_rate_limit_address(&program_id, &bridge_admin, Some(asset_id)).0, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    if let (None, Some(token_seed)) = (&leaf, token_seed) {
+        accounts.push(AccountMeta::new(get_wrapped_asset_address(&program_id, &bridge_admin, token_seed).0, false));
+    }
+
+    accounts.extend(leaf_proof.into_iter().map(|node| AccountMeta::new_readonly(node, false)));
+
     Instruction {
         program_id,
-        accounts: vec![
-            AccountMeta::new_readonly(bridge_admin, false),
-            AccountMeta::new(owner, true),
-            AccountMeta::new(withdraw, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-            AccountMeta::new_readonly(solana_program::system_program::id(), false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
-        ],
-        data: BridgeInstruction::WithdrawNative(WithdrawArgs {
+        accounts,
+        data: BridgeInstruction::WithdrawCNFT(WithdrawCNFTArgs {
             origin,
-            amount,
-            signature,
-            recovery_id,
+            proof,
             path,
             seeds,
+            leaf,
             token_seed,
             signed_meta,
+            bitmap_index,
         }).try_to_vec().unwrap(),
     }
 }
-
-pub fn withdraw_ft(
+pub fn update_wrapped_metadata(
     program_id: Pubkey,
     bridge_admin: Pubkey,
-    mint: Pubkey,
-    owner: Pubkey,
-    withdraw: Pubkey,
+    fee_payer: Pubkey,
     seeds: [u8; 32],
-    origin: [u8; 32],
-    amount: u64,
-    signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    token_seed: [u8; 32],
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Vec<SignedCreator>,
+    signatures: Vec<EcdsaSignature>,
     path: Vec<[u8; 32]>,
-    token_seed: Option<[u8; 32]>,
-    signed_meta: Option<SignedMetadata>,
 ) -> Instruction {
-    let owner_associated = get_associated_token_address(&owner, &mint);
-    let bridge_associated = get_associated_token_address(&bridge_admin, &mint);
+    let mint = Pubkey::find_program_address(&[token_seed.as_slice()], &program_id).0;
 
     Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new_readonly(bridge_admin, false),
             AccountMeta::new_readonly(mint, false),
-            AccountMeta::new(owner, true),
-            AccountMeta::new(owner_associated, false),
-            AccountMeta::new(bridge_associated, false),
-            AccountMeta::new(withdraw, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(Metadata::find_pda(&mint).0, false),
+            AccountMeta::new(get_metadata_nonce_address(&program_id, &bridge_admin, &mint).0, false),
+            AccountMeta::new(fee_payer, true),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
         ],
-        data: BridgeInstruction::WithdrawFT(WithdrawArgs {
-            origin,
-            amount,
-            signature,
-            recovery_id,
+        data: BridgeInstruction::UpdateWrappedMetadata(UpdateWrappedMetadataArgs {
+            signatures,
             path,
-            seeds,
             token_seed,
-            signed_meta,
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points,
+            creators,
+            seeds,
         }).try_to_vec().unwrap(),
     }
 }
-
-pub fn withdraw_nft(
+pub fn migrate_sol_vault(
     program_id: Pubkey,
     bridge_admin: Pubkey,
-    mint: Pubkey,
-    metadata: Pubkey,
-    owner: Pubkey,
-    withdraw: Pubkey,
     seeds: [u8; 32],
-    origin: [u8; 32],
-    amount: u64,
-    signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    recovery_id: u8,
+    signatures: Vec<EcdsaSignature>,
     path: Vec<[u8; 32]>,
-    token_seed: Option<[u8; 32]>,
-    signed_meta: Option<SignedMetadata>,
 ) -> Instruction {
-    let owner_associated = get_associated_token_address(&owner, &mint);
-    let bridge_associated = get_associated_token_address(&bridge_admin, &mint);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new(get_sol_vault_address(&program_id, &bridge_admin).0, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: BridgeInstruction::MigrateSolVault(MigrateSolVaultArgs {
+            signatures,
+            path,
+            seeds,
+        }).try_to_vec().unwrap(),
+    }
+}
 
+pub fn migrate_admin(
+    program_id: Pubkey,
+    bridge_admin: Pubkey,
+    fee_payer: Pubkey,
+    seeds: [u8; 32],
+    signature: EcdsaSignature,
+) -> Instruction {
     Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new_readonly(bridge_admin, false),
-            AccountMeta::new(mint, false),
-            AccountMeta::new(metadata, false),
-            AccountMeta::new(owner, true),
-            AccountMeta::new(owner_associated, false),
-            AccountMeta::new(bridge_associated, false),
-            AccountMeta::new(withdraw, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(bridge_admin, false),
+            AccountMeta::new(fee_payer, true),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
-            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
         ],
-        data: BridgeInstruction::WithdrawNFT(WithdrawArgs {
-            origin,
-            amount,
+        data: BridgeInstruction::MigrateAdmin(MigrateAdminArgs {
             signature,
-            recovery_id,
-            path,
             seeds,
-            token_seed,
-            signed_meta,
         }).try_to_vec().unwrap(),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relayer_fee() {
+        assert!(validate_relayer_fee(None, 100).is_ok());
+        assert!(validate_relayer_fee(Some(0), 100).is_ok());
+        assert!(validate_relayer_fee(Some(99), 100).is_ok());
+
+        // The receiver gets nothing if the fee takes the whole amount
+        assert_eq!(validate_relayer_fee(Some(100), 100), Err(LibError::WrongRelayerFee.into()));
+        assert_eq!(validate_relayer_fee(Some(u64::MAX), 100), Err(LibError::WrongRelayerFee.into()));
+    }
+
+    #[test]
+    fn royalties() {
+        let creators = |shares: &[u8]| -> Vec<SignedCreator> {
+            shares.iter().map(|share| SignedCreator { address: Pubkey::new_unique(), share: *share }).collect()
+        };
+
+        assert!(validate_royalties(0, &creators(&[])).is_ok());
+        assert!(validate_royalties(10000, &creators(&[100])).is_ok());
+        assert!(validate_royalties(500, &creators(&[50, 30, 20, 0, 0])).is_ok());
+
+        assert_eq!(validate_royalties(10001, &creators(&[])), Err(LibError::WrongArgsSize.into()));
+        assert_eq!(validate_royalties(500, &creators(&[50, 49])), Err(LibError::WrongArgsSize.into()));
+        assert_eq!(validate_royalties(500, &creators(&[100, 1])), Err(LibError::WrongArgsSize.into()));
+        assert_eq!(validate_royalties(500, &creators(&[0])), Err(LibError::WrongArgsSize.into()));
+        // Shares must not overflow into a valid sum
+        assert_eq!(validate_royalties(500, &creators(&[255, 101])), Err(LibError::WrongArgsSize.into()));
+        // More than MAX_CREATOR_LIMIT creators
+        assert_eq!(validate_royalties(500, &creators(&[95, 1, 1, 1, 1, 1])), Err(LibError::WrongArgsSize.into()));
+    }
+}
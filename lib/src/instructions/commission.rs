@@ -5,7 +5,7 @@ use solana_program::{
     pubkey::Pubkey,
     sysvar,
 };
-use solana_program::secp256k1_recover::SECP256K1_PUBLIC_KEY_LENGTH;
+use crate::ecdsa::EcdsaSignature;
 use crate::{CommissionToken, CommissionArgs, TokenType};
 use std::mem::size_of;
 use spl_associated_token_account::get_associated_token_address;
@@ -29,8 +29,7 @@ pub struct InitializeAdminArgs {
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct FeeTokenArgs {
-    pub signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    pub recovery_id: u8,
+    pub signatures: Vec<EcdsaSignature>,
     pub path: Vec<[u8; 32]>,
     pub token: CommissionTokenArg,
 }
@@ -38,8 +37,7 @@ pub struct FeeTokenArgs {
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct WithdrawArgs {
-    pub signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    pub recovery_id: u8,
+    pub signatures: Vec<EcdsaSignature>,
     pub path: Vec<[u8; 32]>,
     pub token: CommissionTokenArg,
     pub withdraw_amount: u64,
@@ -79,6 +77,7 @@ pub enum CommissionInstruction {
     ///
     ///   0. `[writable]` The CommissionAdmin account
     ///   1. `[]` The BridgeAdmin account
+    ///   2. `[]` Instructions sysvar
     AddFeeToken(FeeTokenArgs),
 
     /// Remove new acceptable commission token
@@ -87,6 +86,7 @@ pub enum CommissionInstruction {
     ///
     ///   0. `[writable]` The CommissionAdmin account
     ///   1. `[]` The BridgeAdmin account
+    ///   2. `[]` Instructions sysvar
     RemoveFeeToken(FeeTokenArgs),
 
     /// Update certain acceptable commission token
@@ -95,6 +95,7 @@ pub enum CommissionInstruction {
     ///
     ///   0. `[writable]` The CommissionAdmin account
     ///   1. `[]` The BridgeAdmin account
+    ///   2. `[]` Instructions sysvar
     UpdateFeeToken(FeeTokenArgs),
 
     /// Withdraw collected tokens from contract
@@ -106,10 +107,11 @@ pub enum CommissionInstruction {
     ///   2. `[writable, signer]` The receiver account (also fee payer)
     ///   3. `[]` System program
     ///   4. `[]` Rent sysvar
-    ///   5. `[]` SPL token program
-    ///   6. `[]` Commission token receiver associated account (Optional)
-    ///   7. `[]` Commission token admin associated account (Optional)
-    ///   8. `[]` Commission token mint account (Optional)
+    ///   5. `[]` Instructions sysvar
+    ///   6. `[]` SPL token program
+    ///   7. `[]` Commission token receiver associated account (Optional)
+    ///   8. `[]` Commission token admin associated account (Optional)
+    ///   9. `[]` Commission token mint account (Optional)
     Withdraw(WithdrawArgs),
 }
 
@@ -5,7 +5,8 @@ use solana_program::{
     pubkey::Pubkey,
     sysvar,
 };
-use solana_program::secp256k1_recover::{SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH};
+use crate::ecdsa::EcdsaSignature;
+use crate::signers::SignerScheme;
 use std::mem::size_of;
 
 
@@ -13,29 +14,34 @@ use std::mem::size_of;
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct InitializeAdminArgs {
-    // ECDSA public key
-    pub public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
+    // Signer set with the signature scheme
+    pub signers: SignerScheme,
     pub contract: Pubkey,
 }
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct TransferOwnershipArgs {
-    // New ECDSA public key
-    pub new_public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    // Signature of new_public_key by old public key
-    pub signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    pub recovery_id: u8,
+    // New signer set with the signature scheme
+    pub new_signers: SignerScheme,
+    // Signatures of the new signer set hash by the threshold of current signers
+    pub signatures: Vec<EcdsaSignature>,
 }
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct UpgradeArgs {
-    pub signature: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    pub recovery_id: u8,
+    pub signatures: Vec<EcdsaSignature>,
     pub path: Vec<[u8; 32]>,
 }
 
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct MigrateAdminArgs {
+    // Signature of the migration content by the legacy ECDSA public key
+    pub signature: EcdsaSignature,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Clone)]
 pub enum UpgradeInstruction {
     /// Initialize new UpgradeAdmin that will store acceptable token
@@ -53,6 +59,7 @@ pub enum UpgradeInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` The UpgradeAdmin account
+    ///   1. `[]` Instructions sysvar
     TransferOwnership(TransferOwnershipArgs),
 
 
@@ -70,5 +77,18 @@ pub enum UpgradeInstruction {
     ///   5. `[]` Rent sysvar.
     ///   6. `[]` Clock sysvar.
     ///   7. `[]` BPFLoaderUpgradable program
+    ///   8. `[]` Instructions sysvar
     Upgrade(UpgradeArgs),
+
+    /// Migrate the UpgradeAdmin account with the legacy layout (single ECDSA public key)
+    /// into the current one with the Secp256k1 signer set of this key and threshold 1.
+    /// The legacy key signs the upgrade content with the UpgradeAdmin address instead of the buffer
+    /// and the current nonce.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The UpgradeAdmin account
+    ///   1. `[writable,signer]` The fee payer, pays the rent for the new size
+    ///   2. `[]` System program
+    MigrateAdmin(MigrateAdminArgs),
 }
\ No newline at end of file
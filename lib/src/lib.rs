@@ -1,3 +1,4 @@
+#![allow(clippy::too_many_arguments)]
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{AccountInfo, next_account_info},
@@ -5,17 +6,42 @@ use solana_program::{
     program::{invoke, invoke_signed}, pubkey::Pubkey, system_instruction,
     sysvar::{rent::Rent, Sysvar},
 };
-use spl_associated_token_account::create_associated_token_account;
+use spl_associated_token_account::instruction::create_associated_token_account;
 
 pub mod merkle;
 pub mod ecdsa;
+pub mod ed25519;
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod networks;
+pub mod signers;
 
 pub const SOLANA_NETWORK: &str = "Solana";
 
 pub const COMMISSION_ADMIN_PDA_SEED: &str = "commission_admin";
 pub const UPGRADE_ADMIN_PDA_SEED: &str = "upgrade_admin";
+pub const DEPOSIT_PDA_SEED: &str = "deposit";
+pub const CALL_AUTHORITY_PDA_SEED: &str = "call_authority";
+pub const MESSAGE_PDA_SEED: &str = "message";
+pub const NETWORK_REGISTRY_PDA_SEED: &str = "network_registry";
+pub const RATE_LIMIT_PDA_SEED: &str = "rate_limit";
+pub const DEPOSIT_LIMIT_PDA_SEED: &str = "deposit_limit";
+pub const TOKEN_POLICY_PDA_SEED: &str = "token_policy";
+pub const SIGNED_ROOT_PDA_SEED: &str = "signed_root";
+pub const WITHDRAW_BITMAP_PDA_SEED: &str = "withdraw_bitmap";
+pub const METADATA_NONCE_PDA_SEED: &str = "metadata_nonce";
+pub const SOL_VAULT_PDA_SEED: &str = "sol_vault";
+pub const WRAPPED_ASSET_PDA_SEED: &str = "wrapped_asset";
+
+// Metaplex Token Authorization Rules program used by programmable NFTs with a rule set
+pub const TOKEN_AUTH_RULES_PROGRAM_ID: Pubkey = solana_program::pubkey!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+
+// Salt for the bundle executor on the destination chain.
+// Bound to the depositor so nobody else can reuse the same executor.
+pub fn get_bundle_salt(bundle_seed: &[u8; 32], sender: &Pubkey) -> [u8; 32] {
+    solana_program::keccak::hash([bundle_seed.as_slice(), sender.as_ref()].concat().as_slice()).to_bytes()
+}
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
@@ -23,6 +49,23 @@ pub enum TokenType {
     Native,
     FT,
     NFT,
+    // Message without token transfer
+    Message,
+    // Compressed NFT (Bubblegum leaf)
+    CNFT,
+}
+
+impl TokenType {
+    // Bit of the token type in the pause masks
+    pub fn mask(&self) -> u8 {
+        match self {
+            TokenType::Native => 1,
+            TokenType::FT => 1 << 1,
+            TokenType::NFT => 1 << 2,
+            TokenType::Message => 1 << 3,
+            TokenType::CNFT => 1 << 4,
+        }
+    }
 }
 
 #[repr(C)]
@@ -41,6 +84,8 @@ pub struct CommissionArgs {
     pub deposit_token_amount: u64,
 }
 
+// Anyone can transfer lamports to the address before the account is created, and `create_account`
+// fails for accounts with lamports. Such accounts are topped up to the rent-exempt balance, allocated and assigned.
 pub fn call_create_account<'a>(
     payer: &AccountInfo<'a>,
     account: &AccountInfo<'a>,
@@ -52,6 +97,10 @@ pub fn call_create_account<'a>(
 ) -> ProgramResult {
     let rent = Rent::from_account_info(rent_info)?;
 
+    if account.lamports() > 0 {
+        return call_create_funded_account(payer, account, system_program, rent.minimum_balance(space), space, owner, seeds);
+    }
+
     let instruction = system_instruction::create_account(
         payer.key,
         account.key,
@@ -66,13 +115,45 @@ pub fn call_create_account<'a>(
         system_program.clone(),
     ];
 
-    if seeds.len() > 0 {
+    if !seeds.is_empty() {
         invoke_signed(&instruction, &accounts, &[seeds])
     } else {
         invoke(&instruction, &accounts)
     }
 }
 
+fn call_create_funded_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    lamports: u64,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let signers: &[&[&[u8]]] = if !seeds.is_empty() { &[seeds] } else { &[] };
+
+    let required = lamports.saturating_sub(account.lamports());
+    if required > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, required),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        signers,
+    )?;
+
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        signers,
+    )
+}
+
 pub fn call_create_associated_account<'a>(
     payer: &AccountInfo<'a>,
     wallet: &AccountInfo<'a>,
@@ -80,13 +161,14 @@ pub fn call_create_associated_account<'a>(
     account: &AccountInfo<'a>,
     rent_info: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
-    spl_token: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
 ) -> ProgramResult {
     invoke(
         &create_associated_token_account(
             payer.key,
             wallet.key,
             mint.key,
+            token_program.key,
         ),
         &[
             payer.clone(),
@@ -94,7 +176,7 @@ pub fn call_create_associated_account<'a>(
             wallet.clone(),
             mint.clone(),
             system_program.clone(),
-            spl_token.clone(),
+            token_program.clone(),
             rent_info.clone()
         ],
     )
@@ -0,0 +1,243 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::entrypoint::ProgramResult;
+use crate::error::LibError;
+use crate::instructions::bridge::MAX_NETWORKS_SIZE;
+use crate::instructions::InstructionValidation;
+
+pub const MAX_HRP_SIZE: usize = 20;
+pub const MAX_NETWORKS_COUNT: usize = 20;
+pub const NETWORK_SIZE: usize = 4 + MAX_NETWORKS_SIZE + 1 + 4 + MAX_HRP_SIZE;
+
+const EVM_ADDRESS_LENGTH: usize = 20;
+const NEAR_MIN_ACCOUNT_LENGTH: usize = 2;
+const NEAR_MAX_ACCOUNT_LENGTH: usize = 64;
+const BECH32_MAX_LENGTH: usize = 90;
+const BECH32_CHECKSUM_LENGTH: usize = 6;
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub enum NetworkFamily {
+    // 0x-prefixed hex encoded 20 bytes
+    EVM,
+    // Near account id
+    Near,
+    // Base58 encoded 32 bytes
+    Solana,
+    // Bech32 address with the provided human-readable part
+    Cosmos { hrp: String },
+}
+
+impl From<&NetworkFamily> for u8 {
+    fn from(family: &NetworkFamily) -> u8 {
+        match family {
+            NetworkFamily::EVM => 0,
+            NetworkFamily::Near => 1,
+            NetworkFamily::Solana => 2,
+            NetworkFamily::Cosmos { .. } => 3,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Network {
+    // Should be the same as used in `network_to` for deposits
+    pub name: String,
+    pub family: NetworkFamily,
+}
+
+impl Network {
+    pub fn validate_receiver(&self, receiver: &str) -> ProgramResult {
+        let valid = match &self.family {
+            NetworkFamily::EVM => is_evm_address(receiver),
+            NetworkFamily::Near => is_near_account(receiver),
+            NetworkFamily::Solana => is_solana_address(receiver),
+            NetworkFamily::Cosmos { hrp } => is_bech32_address(receiver, hrp),
+        };
+
+        if !valid {
+            return Err(LibError::WrongReceiverAddress.into());
+        }
+
+        Ok(())
+    }
+
+    // Bytes to be signed for network management operations
+    pub fn get_operation(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.append(&mut Vec::from(self.name.as_bytes()));
+        data.push((&self.family).into());
+
+        if let NetworkFamily::Cosmos { hrp } = &self.family {
+            data.append(&mut Vec::from(hrp.as_bytes()));
+        }
+
+        data
+    }
+}
+
+impl InstructionValidation for Network {
+    fn validate(&self) -> ProgramResult {
+        if self.name.is_empty() || self.name.len() > MAX_NETWORKS_SIZE {
+            return Err(LibError::WrongArgsSize.into());
+        }
+
+        if let NetworkFamily::Cosmos { hrp } = &self.family {
+            if hrp.is_empty() || hrp.len() > MAX_HRP_SIZE {
+                return Err(LibError::WrongArgsSize.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn is_evm_address(address: &str) -> bool {
+    match address.strip_prefix("0x") {
+        Some(hex) => hex.len() == EVM_ADDRESS_LENGTH * 2 && hex.bytes().all(|c| c.is_ascii_hexdigit()),
+        None => false,
+    }
+}
+
+// See https://nomicon.io/DataStructures/Account#account-id-rules
+pub fn is_near_account(address: &str) -> bool {
+    if address.len() < NEAR_MIN_ACCOUNT_LENGTH || address.len() > NEAR_MAX_ACCOUNT_LENGTH {
+        return false;
+    }
+
+    let mut prev_separator = true;
+    for c in address.bytes() {
+        let separator = c == b'-' || c == b'_' || c == b'.';
+        if separator && prev_separator {
+            return false;
+        }
+
+        if !separator && !c.is_ascii_lowercase() && !c.is_ascii_digit() {
+            return false;
+        }
+
+        prev_separator = separator;
+    }
+
+    !prev_separator
+}
+
+pub fn is_solana_address(address: &str) -> bool {
+    match bs58::decode(address).into_vec() {
+        Ok(bytes) => bytes.len() == 32,
+        Err(_) => false,
+    }
+}
+
+// See https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki
+pub fn is_bech32_address(address: &str, hrp: &str) -> bool {
+    if address.len() > BECH32_MAX_LENGTH {
+        return false;
+    }
+
+    // Only lowercase form is accepted
+    let (address_hrp, data) = match address.rsplit_once('1') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    if address_hrp != hrp || data.len() < BECH32_CHECKSUM_LENGTH {
+        return false;
+    }
+
+    let mut values = Vec::with_capacity(data.len());
+    for c in data.bytes() {
+        match BECH32_CHARSET.iter().position(|x| *x == c) {
+            Some(v) => values.push(v as u8),
+            None => return false,
+        }
+    }
+
+    let mut checked = Vec::with_capacity(hrp.len() * 2 + 1 + values.len());
+    checked.extend(hrp.bytes().map(|c| c >> 5));
+    checked.push(0);
+    checked.extend(hrp.bytes().map(|c| c & 31));
+    checked.append(&mut values);
+
+    bech32_polymod(&checked) == 1
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+    let mut chk: u32 = 1;
+    for v in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ (*v as u32);
+        for (i, gen) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= gen;
+            }
+        }
+    }
+
+    chk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Valid test vectors of BIP-173
+    const VALID_BECH32: [(&str, &str); 3] = [
+        ("a12uel5l", "a"),
+        ("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw", "abcdef"),
+        ("split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w", "split"),
+    ];
+
+    #[test]
+    fn bech32_polymod_of_valid_vectors() {
+        assert_eq!(bech32_polymod(&[]), 1);
+
+        for (address, hrp) in VALID_BECH32 {
+            let (_, data) = address.rsplit_once('1').unwrap();
+            let mut values: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+            values.push(0);
+            values.extend(hrp.bytes().map(|c| c & 31));
+            values.extend(data.bytes().map(|c| BECH32_CHARSET.iter().position(|x| *x == c).unwrap() as u8));
+
+            assert_eq!(bech32_polymod(&values), 1, "{}", address);
+        }
+    }
+
+    #[test]
+    fn bech32_address() {
+        for (address, hrp) in VALID_BECH32 {
+            assert!(is_bech32_address(address, hrp), "{}", address);
+        }
+
+        // Wrong hrp
+        assert!(!is_bech32_address("a12uel5l", "b"));
+        // Wrong checksum
+        assert!(!is_bech32_address("a12uel5m", "a"));
+        assert!(!is_bech32_address("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxx", "abcdef"));
+        // Uppercase form
+        assert!(!is_bech32_address("A12UEL5L", "a"));
+        // Character out of the charset
+        assert!(!is_bech32_address("a1b2uel5l", "a"));
+        // Missing separator and too short data
+        assert!(!is_bech32_address("a2uel5l", "a"));
+        assert!(!is_bech32_address("a1uel5l", "a"));
+        // Too long
+        assert!(!is_bech32_address(&format!("a1{}", "q".repeat(BECH32_MAX_LENGTH)), "a"));
+    }
+
+    #[test]
+    fn near_account() {
+        for account in ["ok", "bob", "bob.near", "alice-bridge.near", "app_1.alice.testnet", "0123456789"] {
+            assert!(is_near_account(account), "{}", account);
+        }
+
+        for account in ["a", "Bob.near", "bob..near", ".bob", "bob.", "bob-", "_bob", "bob@near", "bob near", &"a".repeat(65)] {
+            assert!(!is_near_account(account), "{}", account);
+        }
+
+        assert!(is_near_account(&"a".repeat(64)));
+    }
+}
@@ -0,0 +1,70 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    sysvar,
+};
+use crate::ecdsa::{EcdsaSignature, SignerSet, MAX_SIGNERS_COUNT, SIGNER_SET_SIZE};
+use crate::ed25519::Ed25519SignerSet;
+use crate::error::LibError;
+use crate::instructions::InstructionValidation;
+
+// The ECDSA signer set is the largest one
+pub const SIGNER_SCHEME_SIZE: usize = 1 + SIGNER_SET_SIZE;
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub enum SignerScheme {
+    // Signatures are passed in the instruction arguments
+    Secp256k1(SignerSet),
+    // Signatures are verified by the preceding Ed25519 program instructions
+    Ed25519(Ed25519SignerSet),
+}
+
+impl SignerScheme {
+    // The instructions sysvar has the fixed slot in every signed instruction regardless of the scheme,
+    // for Ed25519 signatures are ignored
+    pub fn verify(&self, hash: &[u8], signatures: &[EcdsaSignature], instruction_sysvar_info: &AccountInfo) -> ProgramResult {
+        if !sysvar::instructions::check_id(instruction_sysvar_info.key) {
+            return Err(LibError::MissingInstructionsSysvar.into());
+        }
+
+        match self {
+            SignerScheme::Secp256k1(signers) => signers.verify(hash, signatures),
+            SignerScheme::Ed25519(signers) => signers.verify(hash, instruction_sysvar_info),
+        }
+    }
+
+    // Hash to be signed by the current signers to replace them with this scheme
+    pub fn hash(&self) -> Result<[u8; 32], ProgramError> {
+        Ok(solana_program::keccak::hash(self.try_to_vec()?.as_slice()).to_bytes())
+    }
+}
+
+impl InstructionValidation for SignerScheme {
+    fn validate(&self) -> ProgramResult {
+        match self {
+            SignerScheme::Secp256k1(signers) => signers.validate(),
+            SignerScheme::Ed25519(signers) => signers.validate(),
+        }
+    }
+}
+
+pub fn validate_signer_keys<T: PartialEq>(public_keys: &[T], threshold: u8) -> ProgramResult {
+    if public_keys.is_empty() || public_keys.len() > MAX_SIGNERS_COUNT {
+        return Err(LibError::WrongSignerSet.into());
+    }
+
+    if threshold == 0 || threshold as usize > public_keys.len() {
+        return Err(LibError::WrongSignerSet.into());
+    }
+
+    for (i, key) in public_keys.iter().enumerate() {
+        if public_keys[..i].contains(key) {
+            return Err(LibError::WrongSignerSet.into());
+        }
+    }
+
+    Ok(())
+}
@@ -4,12 +4,13 @@ use solana_program::{
     program::{invoke, invoke_signed}, pubkey::Pubkey, system_instruction,
     sysvar::{rent::Rent, Sysvar},
 };
-use crate::state::{MAX_ADMIN_SIZE, UpgradeAdmin};
+use crate::state::{LEGACY_ADMIN_SIZE, MAX_ADMIN_SIZE, LegacyUpgradeAdmin, UpgradeAdmin};
 use borsh::{
     BorshDeserialize, BorshSerialize,
 };
-use solana_program::secp256k1_recover::{SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH};
-use lib::ecdsa::verify_ecdsa_signature;
+use lib::ecdsa::{verify_ecdsa_signature, EcdsaSignature, SignerSet};
+use lib::signers::SignerScheme;
+use lib::instructions::InstructionValidation;
 use lib::error::LibError;
 use lib::instructions::upgrade::UpgradeInstruction;
 use crate::merkle::Content;
@@ -24,15 +25,21 @@ pub fn process_instruction<'a>(
     match instruction {
         UpgradeInstruction::InitializeAdmin(args) => {
             msg!("Instruction: Create Upgrade Admin");
-            process_init_admin(program_id, accounts, args.public_key, args.contract)
+            args.signers.validate()?;
+            process_init_admin(program_id, accounts, args.signers, args.contract)
         }
         UpgradeInstruction::TransferOwnership(args) => {
             msg!("Instruction: Transfer ownership");
-            process_transfer_ownership(program_id, accounts, args.new_public_key, args.signature, args.recovery_id)
+            args.new_signers.validate()?;
+            process_transfer_ownership(program_id, accounts, args.new_signers, args.signatures)
         }
         UpgradeInstruction::Upgrade(args) => {
             msg!("Instruction: Upgrade");
-            process_upgrade(program_id, accounts, args.signature, args.recovery_id, args.path)
+            process_upgrade(program_id, accounts, args.signatures, args.path)
+        }
+        UpgradeInstruction::MigrateAdmin(args) => {
+            msg!("Instruction: Migrate admin");
+            process_migrate_admin(program_id, accounts, args.signature)
         }
     }
 }
@@ -41,7 +48,7 @@ pub fn process_instruction<'a>(
 pub fn process_init_admin<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
-    public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
+    signers: SignerScheme,
     upgrade_program: Pubkey,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -72,7 +79,7 @@ pub fn process_init_admin<'a>(
     }
 
     upgrade_admin.contract = upgrade_program;
-    upgrade_admin.public_key = public_key;
+    upgrade_admin.signers = signers;
     upgrade_admin.is_initialized = true;
     upgrade_admin.serialize(&mut *upgrade_admin_info.data.borrow_mut())?;
     Ok(())
@@ -82,12 +89,12 @@ pub fn process_init_admin<'a>(
 pub fn process_transfer_ownership<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
-    new_public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
-    signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    recovery_id: u8,
+    new_signers: SignerScheme,
+    signatures: Vec<EcdsaSignature>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let upgrade_admin_info = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
 
     let mut upgrade_admin: UpgradeAdmin = BorshDeserialize::deserialize(&mut upgrade_admin_info.data.borrow_mut().as_ref())?;
     if !upgrade_admin.is_initialized {
@@ -99,9 +106,9 @@ pub fn process_transfer_ownership<'a>(
         return Err(LibError::WrongSeeds.into());
     }
 
-    verify_ecdsa_signature(solana_program::keccak::hash(new_public_key.as_slice()).as_ref(), signature.as_slice(), recovery_id, upgrade_admin.public_key)?;
+    upgrade_admin.signers.verify(new_signers.hash()?.as_slice(), &signatures, instruction_sysvar_info)?;
 
-    upgrade_admin.public_key = new_public_key;
+    upgrade_admin.signers = new_signers;
     upgrade_admin.serialize(&mut *upgrade_admin_info.data.borrow_mut())?;
     Ok(())
 }
@@ -109,8 +116,7 @@ pub fn process_transfer_ownership<'a>(
 pub fn process_upgrade<'a>(
     program_id: &'a Pubkey,
     accounts: &'a [AccountInfo<'a>],
-    signature: [u8; SECP256K1_SIGNATURE_LENGTH],
-    recovery_id: u8,
+    signatures: Vec<EcdsaSignature>,
     path: Vec<[u8; 32]>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -121,6 +127,8 @@ pub fn process_upgrade<'a>(
     let upgrade_spill = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
     let clock_info = next_account_info(account_info_iter)?;
+    let _loader_program = next_account_info(account_info_iter)?;
+    let instruction_sysvar_info = next_account_info(account_info_iter)?;
 
     let upgrade_admin_key = Pubkey::create_program_address(&[lib::UPGRADE_ADMIN_PDA_SEED.as_bytes(), upgrade_program.key.as_ref()], &program_id)?;
     if upgrade_admin_key != *upgrade_admin_info.key {
@@ -144,7 +152,7 @@ pub fn process_upgrade<'a>(
     let content = Content::new(upgrade_admin.nonce, upgrade_admin.contract, *upgrade_buffer.key);
     let root = get_merkle_root(content.hash(), &path)?;
 
-    verify_ecdsa_signature(root.as_ref(), signature.as_slice(), recovery_id, upgrade_admin.public_key)?;
+    upgrade_admin.signers.verify(root.as_ref(), &signatures, instruction_sysvar_info)?;
 
     invoke_signed(
         &instruction,
@@ -164,4 +172,60 @@ pub fn process_upgrade<'a>(
     upgrade_admin.nonce = upgrade_admin.nonce + 1;
     upgrade_admin.serialize(&mut *upgrade_admin_info.data.borrow_mut())?;
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn process_migrate_admin<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    signature: EcdsaSignature,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let upgrade_admin_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if upgrade_admin_info.data_len() != LEGACY_ADMIN_SIZE {
+        return Err(LibError::AlreadyMigrated.into());
+    }
+
+    let legacy: LegacyUpgradeAdmin = BorshDeserialize::deserialize(&mut upgrade_admin_info.data.borrow().as_ref())?;
+    if !legacy.is_initialized {
+        return Err(LibError::NotInitialized.into());
+    }
+
+    let upgrade_admin_key = Pubkey::create_program_address(&[lib::UPGRADE_ADMIN_PDA_SEED.as_bytes(), legacy.contract.as_ref()], program_id)?;
+    if upgrade_admin_key != *upgrade_admin_info.key {
+        return Err(LibError::WrongSeeds.into());
+    }
+
+    // The UpgradeAdmin address can not be a buffer, so the signature is not valid for the upgrade
+    let content = Content::new(legacy.nonce, legacy.contract, upgrade_admin_key);
+    verify_ecdsa_signature(content.hash().as_ref(), signature.signature.as_slice(), signature.recovery_id, legacy.public_key)?;
+
+    let required = Rent::get()?.minimum_balance(MAX_ADMIN_SIZE).saturating_sub(upgrade_admin_info.lamports());
+    if required > 0 {
+        msg!("Paying rent for the new admin size");
+        invoke(
+            &system_instruction::transfer(fee_payer_info.key, upgrade_admin_info.key, required),
+            &[
+                fee_payer_info.clone(),
+                upgrade_admin_info.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+
+    upgrade_admin_info.realloc(MAX_ADMIN_SIZE, true)?;
+
+    let upgrade_admin = UpgradeAdmin {
+        signers: SignerScheme::Secp256k1(SignerSet {
+            public_keys: vec![legacy.public_key],
+            threshold: 1,
+        }),
+        contract: legacy.contract,
+        nonce: legacy.nonce + 1,
+        is_initialized: true,
+    };
+    upgrade_admin.serialize(&mut *upgrade_admin_info.data.borrow_mut())?;
+    Ok(())
+}
@@ -2,16 +2,29 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
 use lib::instructions::commission::{MAX_TOKENS_COUNT, MAX_TOKEN_SIZE};
 use std::mem::size_of;
+use lib::signers::{SignerScheme, SIGNER_SCHEME_SIZE};
 use solana_program::secp256k1_recover::SECP256K1_PUBLIC_KEY_LENGTH;
 
-pub const MAX_ADMIN_SIZE: usize = SECP256K1_PUBLIC_KEY_LENGTH + (32 as usize) + (8 as usize) + (1 as usize);
+pub const MAX_ADMIN_SIZE: usize = SIGNER_SCHEME_SIZE + 32 + 8 + 1;
+pub const LEGACY_ADMIN_SIZE: usize = SECP256K1_PUBLIC_KEY_LENGTH + 32 + 8 + 1;
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct UpgradeAdmin {
+    // Signer set with the signature scheme
+    pub signers: SignerScheme,
+    pub contract: Pubkey,
+    pub nonce: u64,
+    pub is_initialized: bool,
+}
+
+// UpgradeAdmin layout before the signer sets, see `MigrateAdmin` instruction
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct LegacyUpgradeAdmin {
     // ECDSA public key
     pub public_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH],
     pub contract: Pubkey,
     pub nonce: u64,
     pub is_initialized: bool,
-}
\ No newline at end of file
+}